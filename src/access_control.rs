@@ -0,0 +1,65 @@
+use crate::opcodes::disassemble;
+use ethers::abi::Abi;
+use std::collections::HashSet;
+
+/// `owner()` and `transferOwnership(address)`
+const OWNABLE_SELECTORS: [[u8; 4]; 2] = [[0x8d, 0xa5, 0xcb, 0x5b], [0xf2, 0xfd, 0xe3, 0x8b]];
+
+/// `pendingOwner()` and `acceptOwnership()`
+const OWNABLE_2_STEP_SELECTORS: [[u8; 4]; 2] = [[0xe3, 0x0c, 0x39, 0x78], [0x79, 0xba, 0x50, 0x97]];
+
+/// `hasRole(bytes32,address)`, `grantRole(bytes32,address)` and `revokeRole(bytes32,address)`
+const ACCESS_CONTROL_SELECTORS: [[u8; 4]; 3] = [[0x91, 0xd1, 0x48, 0x54], [0x2f, 0x2f, 0xf1, 0x5d], [0xd5, 0x47, 0x74, 0x1f]];
+
+/**
+ * Common ownership and role based access control patterns
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessControlPattern {
+    /// A single owner, e.g. OpenZeppelin `Ownable` and its `onlyOwner` modifier
+    Ownable,
+    /// Ownership transfers that have to be accepted by the new owner, OpenZeppelin `Ownable2Step`
+    Ownable2Step,
+    /// Roles, OpenZeppelin `AccessControl`
+    AccessControl,
+}
+
+impl std::fmt::Display for AccessControlPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessControlPattern::Ownable => write!(f, "Ownable"),
+            AccessControlPattern::Ownable2Step => write!(f, "Ownable2Step"),
+            AccessControlPattern::AccessControl => write!(f, "AccessControl"),
+        }
+    }
+}
+
+/**
+ * Finds the access control patterns of a contract. A pattern is detected when all of its functions are either
+ * in the ABI or their selectors are pushed by the bytecode, so contracts without a (valid) ABI can still be checked.
+ */
+pub fn detect_access_control(abi_json: &str, bytecode: &[u8]) -> Vec<AccessControlPattern> {
+    let mut selectors: HashSet<[u8; 4]> = match serde_json::from_str::<Abi>(abi_json) {
+        Ok(abi) => abi.functions().map(|function| function.short_signature()).collect(),
+        Err(_) => HashSet::new(),
+    };
+    for instruction in disassemble(bytecode) {
+        if let Ok(selector) = <[u8; 4]>::try_from(instruction.push_data.as_slice()) {
+            selectors.insert(selector);
+        }
+    }
+    let implements = |required: &[[u8; 4]]| required.iter().all(|selector| selectors.contains(selector));
+
+    let mut patterns = Vec::new();
+    if implements(&OWNABLE_SELECTORS) {
+        patterns.push(AccessControlPattern::Ownable);
+    }
+    if implements(&OWNABLE_2_STEP_SELECTORS) {
+        patterns.push(AccessControlPattern::Ownable2Step);
+    }
+    if implements(&ACCESS_CONTROL_SELECTORS) {
+        patterns.push(AccessControlPattern::AccessControl);
+    }
+
+    patterns
+}
@@ -0,0 +1,84 @@
+use crate::{
+    metadata::ContractMetadata,
+    opcodes::{contains_delegatecall, contains_selfdestruct},
+};
+
+/**
+ * Inspects a single bytecode, either init code or deployed code
+ */
+#[derive(Debug, Clone)]
+pub struct BytecodeAnalyzer {
+    bytecode: Vec<u8>,
+}
+
+impl BytecodeAnalyzer {
+    pub fn new(bytecode: impl Into<Vec<u8>>) -> Self {
+        BytecodeAnalyzer {
+            bytecode: bytecode.into(),
+        }
+    }
+
+    /**
+     * Everything before the CBOR metadata, for init code this also drops the constructor arguments that follow it.
+     * Bytecode without metadata is returned as is.
+     */
+    pub fn strip_metadata(&self) -> &[u8] {
+        match ContractMetadata::find(&self.bytecode) {
+            Some((range, _)) => &self.bytecode[..range.start],
+            None => &self.bytecode,
+        }
+    }
+
+    /**
+     * Only the code before the metadata is scanned, the bytes of the metadata hash would be read as instructions
+     */
+    pub fn has_selfdestruct(&self) -> bool {
+        contains_selfdestruct(self.strip_metadata())
+    }
+
+    pub fn has_delegatecall(&self) -> bool {
+        contains_delegatecall(self.strip_metadata())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BytecodeAnalyzer;
+    use ethers::utils::hex;
+
+    /// `{"ipfs": <34 bytes>, "solc": 0.8.19}` whose hash is made of `SELFDESTRUCT` and `DELEGATECALL` bytes
+    const METADATA: &str = concat!(
+        "a2646970667358221220",
+        "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "64736f6c6343000813",
+        "0033"
+    );
+
+    fn analyzer(hex_string: &str) -> BytecodeAnalyzer {
+        BytecodeAnalyzer::new(hex::decode(hex_string).unwrap())
+    }
+
+    #[test]
+    fn strips_the_metadata() {
+        assert_eq!(analyzer(&format!("6080604052{}", METADATA)).strip_metadata(), [0x60, 0x80, 0x60, 0x40, 0x52]);
+        assert_eq!(analyzer("6080604052").strip_metadata(), [0x60, 0x80, 0x60, 0x40, 0x52]);
+    }
+
+    #[test]
+    fn ignores_opcodes_in_the_metadata() {
+        let metadata = METADATA.replacen("ffffffffffffffff", "f4f4f4f4f4f4f4f4", 1);
+        let analyzer = analyzer(&format!("6080604052{}", metadata));
+
+        assert!(!analyzer.has_selfdestruct());
+        assert!(!analyzer.has_delegatecall());
+    }
+
+    #[test]
+    fn finds_opcodes_in_the_code() {
+        // `SELFDESTRUCT` and `DELEGATECALL`, but only as `PUSH2` data in the second bytecode
+        assert!(analyzer(&format!("60806040ff{}", METADATA)).has_selfdestruct());
+        assert!(analyzer(&format!("60806040f4{}", METADATA)).has_delegatecall());
+        assert!(!analyzer(&format!("6080604061fff4{}", METADATA)).has_selfdestruct());
+        assert!(!analyzer(&format!("6080604061fff4{}", METADATA)).has_delegatecall());
+    }
+}
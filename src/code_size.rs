@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+/// EIP-3860: since Shanghai a `CREATE` with more init code than this fails
+pub const MAX_INIT_CODE_SIZE: usize = 49_152;
+/// EIP-170: since Spurious Dragon a deployment that returns more code than this fails
+pub const MAX_CODE_SIZE: usize = 24_576;
+
+/**
+ * The size of one of the compiled or on-chain bytecodes, with the limit that applies to it
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeSize {
+    /// e.g. `compiled init code` or `on-chain deployed code`
+    pub code: String,
+    pub size: usize,
+    pub limit: usize,
+}
+
+impl CodeSize {
+    pub fn init_code(source: &str, code: &[u8]) -> CodeSize {
+        CodeSize { code: format!("{} init code", source), size: code.len(), limit: MAX_INIT_CODE_SIZE }
+    }
+
+    pub fn deployed_code(source: &str, code: &[u8]) -> CodeSize {
+        CodeSize { code: format!("{} deployed code", source), size: code.len(), limit: MAX_CODE_SIZE }
+    }
+
+    pub fn exceeds_limit(&self) -> bool {
+        self.size > self.limit
+    }
+}
+
+impl std::fmt::Display for CodeSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} bytes (limit {} bytes)", self.code, self.size, self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodeSize, MAX_CODE_SIZE, MAX_INIT_CODE_SIZE};
+
+    #[test]
+    fn checks_the_limits() {
+        assert!(!CodeSize::init_code("compiled", &vec![0; MAX_INIT_CODE_SIZE]).exceeds_limit());
+        assert!(CodeSize::init_code("compiled", &vec![0; MAX_INIT_CODE_SIZE + 1]).exceeds_limit());
+        assert!(CodeSize::deployed_code("on-chain", &vec![0; MAX_CODE_SIZE + 1]).exceeds_limit());
+        assert_eq!(
+            CodeSize::deployed_code("on-chain", &[0; 10]).to_string(),
+            "on-chain deployed code: 10 bytes (limit 24576 bytes)"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use eyre::Result;
+
+/**
+ * A contract as forge selects it: only its name, or `<path>:<name>` when several contracts share the name
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForgeContractRef {
+    /// Source file relative to the project root, e.g. `src/MyContract.sol`
+    pub path: Option<String>,
+    pub name: String,
+}
+
+impl std::fmt::Display for ForgeContractRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path.as_ref() {
+            Some(path) => write!(f, "{}:{}", path, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/**
+ * Parses `--contract-name`. The name has to be a Solidity identifier and the path may only contain the characters of
+ * ordinary file names, so neither can be mistaken for an option or reach outside of the project.
+ */
+pub fn validate_contract_name(name: &str) -> Result<ForgeContractRef> {
+    let (path, contract) = match name.trim().rsplit_once(':') {
+        Some((path, contract)) => (Some(path), contract),
+        None => (None, name.trim()),
+    };
+
+    let mut chars = contract.chars();
+    let is_identifier = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if !is_identifier {
+        return Err(eyre::eyre!("Invalid contract name '{}', expected a Solidity identifier", contract));
+    }
+
+    if let Some(path) = path {
+        let valid_chars = path.chars().all(|c| c.is_ascii_alphanumeric() || "/._-@+".contains(c));
+        if !valid_chars || path.is_empty() || path.starts_with('-') || path.starts_with('/') || path.split('/').any(|part| part == "..") {
+            return Err(eyre::eyre!("Invalid contract path '{}', expected a path relative to the project like src/MyContract.sol", path));
+        }
+    }
+
+    Ok(ForgeContractRef {
+        path: path.map(String::from),
+        name: contract.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_contract_name;
+
+    #[test]
+    fn parses_qualified_names() {
+        let contract = validate_contract_name("src/tokens/Token.sol:Token").unwrap();
+        assert_eq!(contract.path.as_deref(), Some("src/tokens/Token.sol"));
+        assert_eq!(contract.name, "Token");
+        assert_eq!(contract.to_string(), "src/tokens/Token.sol:Token");
+
+        assert_eq!(validate_contract_name("Token").unwrap().path, None);
+    }
+
+    #[test]
+    fn rejects_unsafe_names() {
+        assert!(validate_contract_name("Token; rm -rf /").is_err());
+        assert!(validate_contract_name("--force").is_err());
+        assert!(validate_contract_name("../../etc/Token.sol:Token").is_err());
+        assert!(validate_contract_name("src/$(id).sol:Token").is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{opcodes::disassemble, signatures::canonical_signature};
+use ethers::{
+    abi::{self, Abi, Token},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest},
+    utils::id,
+};
+use eyre::Result;
+use std::str;
+
+const ERC20_REQUIRED: [&str; 6] = [
+    "totalSupply()",
+    "balanceOf(address)",
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "allowance(address,address)",
+];
+
+const ERC20_OPTIONAL: [&str; 3] = ["name()", "symbol()", "decimals()"];
+
+const ERC721_REQUIRED: [&str; 10] = [
+    "balanceOf(address)",
+    "ownerOf(uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+    "safeTransferFrom(address,address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "setApprovalForAll(address,bool)",
+    "getApproved(uint256)",
+    "isApprovedForAll(address,address)",
+    "supportsInterface(bytes4)",
+];
+
+/// The metadata and enumerable extensions
+const ERC721_OPTIONAL: [&str; 6] = [
+    "name()",
+    "symbol()",
+    "tokenURI(uint256)",
+    "totalSupply()",
+    "tokenByIndex(uint256)",
+    "tokenOfOwnerByIndex(address,uint256)",
+];
+
+const ERC1155_REQUIRED: [&str; 7] = [
+    "safeTransferFrom(address,address,uint256,uint256,bytes)",
+    "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+    "balanceOf(address,uint256)",
+    "balanceOfBatch(address[],uint256[])",
+    "setApprovalForAll(address,bool)",
+    "isApprovedForAll(address,address)",
+    "supportsInterface(bytes4)",
+];
+
+/// The metadata URI extension
+const ERC1155_OPTIONAL: [&str; 1] = ["uri(uint256)"];
+
+/// In addition to the ERC-20 functions
+const ERC4626_REQUIRED: [&str; 16] = [
+    "asset()",
+    "totalAssets()",
+    "convertToShares(uint256)",
+    "convertToAssets(uint256)",
+    "maxDeposit(address)",
+    "previewDeposit(uint256)",
+    "deposit(uint256,address)",
+    "maxMint(address)",
+    "previewMint(uint256)",
+    "mint(uint256,address)",
+    "maxWithdraw(address)",
+    "previewWithdraw(uint256)",
+    "withdraw(uint256,address,address)",
+    "maxRedeem(address)",
+    "previewRedeem(uint256)",
+    "redeem(uint256,address,address)",
+];
+
+/// ERC-165 interface ids, the XOR of the selectors of the standard (without `supportsInterface`)
+const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+/**
+ * Token standards a contract can be checked against with `--check-contract-type`
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContractStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+    Erc4626,
+}
+
+impl str::FromStr for ContractStandard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // `ERC-20` is how it is displayed, which is also what `to_cli_args` passes on
+        match s.to_lowercase().replace('-', "").as_str() {
+            "erc20" => Ok(ContractStandard::Erc20),
+            "erc721" => Ok(ContractStandard::Erc721),
+            "erc1155" => Ok(ContractStandard::Erc1155),
+            "erc4626" => Ok(ContractStandard::Erc4626),
+            _ => Err(format!("Unknown contract type '{}', expected one of: erc20, erc721, erc1155, erc4626", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for ContractStandard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContractStandard::Erc20 => write!(f, "ERC-20"),
+            ContractStandard::Erc721 => write!(f, "ERC-721"),
+            ContractStandard::Erc1155 => write!(f, "ERC-1155"),
+            ContractStandard::Erc4626 => write!(f, "ERC-4626"),
+        }
+    }
+}
+
+impl interactive_clap::ToCli for ContractStandard {
+    type CliVariant = ContractStandard;
+}
+
+impl ContractStandard {
+    fn required(&self) -> Vec<&'static str> {
+        match self {
+            ContractStandard::Erc20 => ERC20_REQUIRED.to_vec(),
+            ContractStandard::Erc721 => ERC721_REQUIRED.to_vec(),
+            ContractStandard::Erc1155 => ERC1155_REQUIRED.to_vec(),
+            ContractStandard::Erc4626 => [ERC20_REQUIRED.as_slice(), ERC4626_REQUIRED.as_slice()].concat(),
+        }
+    }
+
+    fn optional(&self) -> Vec<&'static str> {
+        match self {
+            ContractStandard::Erc20 | ContractStandard::Erc4626 => ERC20_OPTIONAL.to_vec(),
+            ContractStandard::Erc721 => ERC721_OPTIONAL.to_vec(),
+            ContractStandard::Erc1155 => ERC1155_OPTIONAL.to_vec(),
+        }
+    }
+
+    /// Only standards that require ERC-165 have an interface id
+    fn interface_id(&self) -> Option<[u8; 4]> {
+        match self {
+            ContractStandard::Erc721 => Some(ERC721_INTERFACE_ID),
+            ContractStandard::Erc1155 => Some(ERC1155_INTERFACE_ID),
+            ContractStandard::Erc20 | ContractStandard::Erc4626 => None,
+        }
+    }
+}
+
+/**
+ * Which functions of the standard the contract implements
+ */
+#[derive(Debug, Clone)]
+pub struct ComplianceReport {
+    pub present: Vec<String>,
+    pub missing: Vec<String>,
+    pub optional_present: Vec<String>,
+    /// Functions in the ABI with the name of a standard function but a different signature
+    pub overloads: Vec<String>,
+    /// The result of `supportsInterface` with the interface id of the standard, `None` if the standard has none
+    pub supports_interface: Option<bool>,
+}
+
+impl ComplianceReport {
+    pub fn is_compliant(&self) -> bool {
+        self.missing.is_empty() && self.supports_interface != Some(false)
+    }
+}
+
+/**
+ * Checks the deployed contract against a token standard
+ */
+pub struct ContractTypeChecker {
+    standard: ContractStandard,
+}
+
+impl ContractTypeChecker {
+    pub fn new(standard: ContractStandard) -> ContractTypeChecker {
+        ContractTypeChecker { standard }
+    }
+
+    /**
+     * A function is implemented when it is in the ABI or its selector is used by the function dispatcher of the deployed code
+     */
+    pub async fn check<M: Middleware>(&self, provider: &M, address: Address, abi: &Abi) -> Result<ComplianceReport> {
+        let code = provider
+            .get_code(address, None)
+            .await
+            .map_err(|err| eyre::eyre!("Could not fetch the code of {:?}: {}", address, err))?;
+        let pushed: Vec<Vec<u8>> = disassemble(&code).into_iter().map(|instruction| instruction.push_data).collect();
+
+        let abi_signatures: Vec<(String, String)> = abi.functions()
+            .map(|function| (function.name.clone(), canonical_signature(&function.name, &function.inputs)))
+            .collect();
+        let implements = |signature: &str| {
+            abi_signatures.iter().any(|(_, abi_signature)| abi_signature == signature)
+                || pushed.iter().any(|data| data.as_slice() == id(signature).as_slice())
+        };
+
+        let required = self.standard.required();
+        let optional = self.standard.optional();
+        let (present, missing): (Vec<&str>, Vec<&str>) = required.iter().copied().partition(|signature| implements(signature));
+        let optional_present: Vec<&str> = optional.iter().copied().filter(|signature| implements(signature)).collect();
+
+        let standard_signatures: Vec<&str> = required.iter().chain(optional.iter()).copied().collect();
+        let overloads = abi_signatures
+            .iter()
+            .filter(|(name, signature)| {
+                !standard_signatures.contains(&signature.as_str())
+                    && standard_signatures.iter().any(|standard| standard.split('(').next() == Some(name.as_str()))
+            })
+            .map(|(_, signature)| signature.clone())
+            .collect();
+
+        let supports_interface = match self.standard.interface_id() {
+            Some(interface_id) => Some(supports_interface(provider, address, interface_id).await),
+            None => None,
+        };
+
+        Ok(ComplianceReport {
+            present: present.into_iter().map(String::from).collect(),
+            missing: missing.into_iter().map(String::from).collect(),
+            optional_present: optional_present.into_iter().map(String::from).collect(),
+            overloads,
+            supports_interface,
+        })
+    }
+}
+
+/**
+ * Calls `supportsInterface(bytes4)`, a revert or any result other than `true` counts as not supported
+ */
+async fn supports_interface<M: Middleware>(provider: &M, address: Address, interface_id: [u8; 4]) -> bool {
+    let mut calldata = id("supportsInterface(bytes4)").to_vec();
+    calldata.extend(abi::encode(&[Token::FixedBytes(interface_id.to_vec())]));
+
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(address)
+        .data(calldata)
+        .into();
+
+    match provider.call(&tx, None).await {
+        Ok(result) => result.len() == 32 && result[..31].iter().all(|byte| *byte == 0) && result[31] == 1,
+        Err(_) => false,
+    }
+}
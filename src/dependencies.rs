@@ -0,0 +1,94 @@
+use crate::interrupt::run_command;
+use eyre::Result;
+use std::{path::Path, process::Command};
+
+/**
+ * A library the project depends on, either a git submodule in `lib/` or an npm package
+ */
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name: String,
+    /// Version from the `package.json` of the dependency
+    pub version: Option<String>,
+    /// Tag of the checked out commit, only for git submodules
+    pub git_tag: Option<String>,
+}
+
+impl Dependency {
+    /**
+     * The `package.json` version and the tag of the pinned commit should describe the same release
+     */
+    pub fn is_consistent(&self) -> bool {
+        match (&self.version, &self.git_tag) {
+            (Some(version), Some(tag)) => tag.trim_start_matches('v') == version,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DependencyVersionReport {
+    pub dependencies: Vec<Dependency>,
+}
+
+impl DependencyVersionReport {
+    /**
+     * Dependencies whose pinned commit is not the release their `package.json` claims to be
+     */
+    pub fn inconsistent(&self) -> Vec<&Dependency> {
+        self.dependencies.iter().filter(|dependency| !dependency.is_consistent()).collect()
+    }
+}
+
+/**
+ * Collects the versions of the OpenZeppelin style dependencies of the project, from the git submodules in `lib/`
+ * and the packages in `node_modules/@openzeppelin/`
+ */
+pub fn check_dependency_versions(git: &Path, project_path: &Path) -> Result<DependencyVersionReport> {
+    let mut report = DependencyVersionReport::default();
+
+    for (folder, is_submodule) in [(project_path.join("lib"), true), (project_path.join("node_modules").join("@openzeppelin"), false)] {
+        let entries = match std::fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            let git_tag = if is_submodule { describe_tag(git, &path)? } else { None };
+            report.dependencies.push(Dependency {
+                name: entry.file_name().to_string_lossy().to_string(),
+                version: read_package_version(&path),
+                git_tag,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+fn read_package_version(path: &Path) -> Option<String> {
+    let package_json = std::fs::read_to_string(path.join("package.json")).ok()?;
+    let package_json: serde_json::Value = serde_json::from_str(&package_json).ok()?;
+
+    package_json["version"].as_str().map(|version| version.to_string())
+}
+
+/**
+ * The tag that points at the checked out commit, if any
+ */
+fn describe_tag(git: &Path, path: &Path) -> Result<Option<String>> {
+    let output = run_command(Command::new(git)
+        .args(["describe", "--tags", "--exact-match"])
+        .current_dir(path))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
@@ -0,0 +1,246 @@
+use ethers::{
+    providers::Middleware,
+    types::{Action, Address, Bytes, Create, CreateResult, Res, Trace, H256},
+    utils::{get_contract_address, get_create2_address_from_hash},
+};
+use crate::trace_provider::{debug_trace_transaction, trace_transaction, trace_transaction_cached, TraceProvider};
+use eyre::Result;
+use std::sync::Arc;
+
+/**
+ * A contract that was created by a `CREATE` or `CREATE2` call
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractDeployment {
+    /// Address of the created contract
+    pub address: Address,
+    /// The account or contract that created the contract
+    pub deployer: Address,
+    /// Init code, including the constructor arguments
+    pub init_code: Bytes,
+    /// Code that ended up being deployed
+    pub deployed_code: Bytes,
+    /// Position of the trace in the trace tree
+    pub trace_address: Vec<usize>,
+    pub transaction_hash: Option<H256>,
+}
+
+/**
+ * The address of a contract created with `CREATE`: `keccak256(rlp([deployer, nonce]))[12..]`
+ */
+pub fn compute_create_address(deployer: Address, nonce: u64) -> Address {
+    get_contract_address(deployer, nonce)
+}
+
+/**
+ * The address of a contract created with `CREATE2`: `keccak256(0xff ++ factory ++ salt ++ keccak256(init_code))[12..]`
+ */
+pub fn compute_create2_address(factory: Address, salt: H256, init_code_hash: H256) -> Address {
+    get_create2_address_from_hash(factory, salt.as_bytes(), init_code_hash.as_bytes())
+}
+
+impl ContractDeployment {
+    /**
+     * Converts a trace into a deployment, returns `None` for traces that did not successfully create a contract
+     */
+    pub fn from_trace(trace: &Trace) -> Option<ContractDeployment> {
+        let (from, init) = match &trace.action {
+            Action::Create(Create { from, init, .. }) => (*from, init.clone()),
+            _ => return None,
+        };
+
+        // Failed creations have no result
+        let (address, code) = match trace.result.as_ref()? {
+            Res::Create(CreateResult { address, code, .. }) => (*address, code.clone()),
+            _ => return None,
+        };
+
+        Some(ContractDeployment {
+            address,
+            deployer: from,
+            init_code: init,
+            deployed_code: code,
+            trace_address: trace.trace_address.clone(),
+            transaction_hash: trace.transaction_hash,
+        })
+    }
+}
+
+/**
+ * Searches the traces of the chain for contract deployments
+ */
+pub struct DeploymentFinder<M: Middleware> {
+    client: Arc<M>,
+    /// Timeout in milliseconds to retry tracing with when the RPC gives up on the trace
+    trace_timeout: Option<u64>,
+    trace_provider: TraceProvider,
+    /// The RPC url, the provider type that worked for it is cached in `Auto` mode
+    rpc: Option<String>,
+    /// Deepest level of the trace tree to search, `0` is the top-level call
+    max_depth: Option<usize>,
+}
+
+impl<M: Middleware> DeploymentFinder<M> {
+    pub fn new(client: Arc<M>) -> Self {
+        DeploymentFinder {
+            client,
+            trace_timeout: None,
+            trace_provider: TraceProvider::Auto,
+            rpc: None,
+            max_depth: None,
+        }
+    }
+
+    /**
+     * Sets the timeout to retry with when tracing runs out of gas or times out
+     */
+    pub fn with_trace_timeout(mut self, trace_timeout: Option<u64>) -> Self {
+        self.trace_timeout = trace_timeout;
+        self
+    }
+
+    /**
+     * Selects `trace_transaction` or `debug_traceTransaction`, with the RPC url the detected one is cached for
+     */
+    pub fn with_trace_provider(mut self, trace_provider: TraceProvider, rpc: Option<String>) -> Self {
+        self.trace_provider = trace_provider;
+        self.rpc = rpc;
+        self
+    }
+
+    /**
+     * Only searches the first levels of the trace tree, `0` is the top-level call and `1` includes its direct subcalls
+     */
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /**
+     * The length of the trace address is the depth of the trace
+     */
+    fn within_depth(&self, trace: &Trace) -> bool {
+        self.max_depth.is_none_or(|max_depth| trace.trace_address.len() <= max_depth)
+    }
+
+    /**
+     * Finds all contracts that were deployed in the transaction
+     */
+    pub async fn find_by_tx(&self, tx_hash: H256) -> Result<Vec<ContractDeployment>> {
+        let traces = self.trace_transaction(tx_hash).await?;
+        Ok(self.deployments(&traces))
+    }
+
+    /**
+     * The raw traces of the transaction, retried with the trace timeout when the provider gives up
+     */
+    pub async fn trace_transaction(&self, tx_hash: H256) -> Result<Vec<Trace>> {
+        let traced = match self.rpc.as_deref() {
+            Some(rpc) => trace_transaction_cached(self.client.as_ref(), tx_hash, self.trace_provider, rpc).await,
+            None => trace_transaction(self.client.as_ref(), tx_hash, self.trace_provider).await,
+        };
+        let traces = match traced {
+            Ok(traces) => traces,
+            // `trace_transaction` takes no limits, only the tracer config of `debug_traceTransaction` has a timeout
+            Err(err) if is_trace_limit_error(&err.to_string()) && self.trace_timeout.is_some() => {
+                debug_trace_transaction(self.client.as_ref(), tx_hash, self.trace_timeout).await?
+            }
+            Err(err) => return Err(err),
+        };
+
+        Ok(traces)
+    }
+
+    /**
+     * The successful contract creations among the traces, within the maximum depth
+     */
+    pub fn deployments(&self, traces: &[Trace]) -> Vec<ContractDeployment> {
+        traces
+            .iter()
+            .filter(|trace| self.within_depth(trace))
+            .filter_map(ContractDeployment::from_trace)
+            .collect()
+    }
+
+}
+
+/**
+ * Checks if the RPC error is caused by the provider limiting the trace
+ */
+fn is_trace_limit_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("execution timeout") || message.contains("out of gas")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeploymentFinder;
+    use crate::{
+        mock_provider::{create_trace, MockProvider},
+        trace_provider::TraceProvider,
+    };
+    use ethers::types::{Address, H256};
+    use std::sync::Arc;
+
+    const INIT_CODE: [u8; 4] = [0x60, 0x80, 0x60, 0x40];
+    const CODE: [u8; 3] = [0x33, 0x50, 0x00];
+
+    fn finder(mock: MockProvider) -> DeploymentFinder<MockProvider> {
+        DeploymentFinder::new(Arc::new(mock)).with_trace_provider(TraceProvider::Parity, None)
+    }
+
+    fn deployment_of(address: Address) -> ethers::types::Trace {
+        create_trace(Address::repeat_byte(1), address, &INIT_CODE, &CODE, Vec::new())
+    }
+
+    #[tokio::test]
+    async fn finds_no_trace() {
+        let finder = finder(MockProvider::default());
+
+        assert!(finder.find_by_tx(H256::repeat_byte(1)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn finds_a_single_deployment() {
+        let (tx_hash, contract) = (H256::repeat_byte(1), Address::repeat_byte(2));
+        let mock = MockProvider::default().with_traces(tx_hash, vec![deployment_of(contract)]);
+
+        let deployments = finder(mock).find_by_tx(tx_hash).await.unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].address, contract);
+        assert_eq!(deployments[0].init_code.to_vec(), INIT_CODE);
+        assert_eq!(deployments[0].deployed_code.to_vec(), CODE);
+    }
+
+    #[tokio::test]
+    async fn finds_every_deployment_of_a_factory() {
+        let (tx_hash, contract) = (H256::repeat_byte(1), Address::repeat_byte(2));
+        let factory = Address::repeat_byte(3);
+        let mock = MockProvider::default().with_traces(
+            tx_hash,
+            vec![
+                create_trace(Address::repeat_byte(1), factory, &INIT_CODE, &CODE, Vec::new()),
+                create_trace(factory, contract, &INIT_CODE, &CODE, vec![0]),
+                create_trace(factory, Address::repeat_byte(4), &INIT_CODE, &CODE, vec![1]),
+            ],
+        );
+
+        let finder = finder(mock);
+        let deployments = finder.find_by_tx(tx_hash).await.unwrap();
+        assert_eq!(deployments.len(), 3);
+        assert_eq!(deployments.iter().filter(|deployment| deployment.deployer == factory).count(), 2);
+
+        // Only the factory itself is a top-level deployment
+        let shallow = finder.with_max_depth(Some(0));
+        assert_eq!(shallow.find_by_tx(tx_hash).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_network_errors() {
+        let tx_hash = H256::repeat_byte(1);
+        let mock = MockProvider::default().with_trace_error(tx_hash, "connection refused");
+
+        let err = finder(mock).find_by_tx(tx_hash).await.unwrap_err();
+        assert!(err.to_string().contains("connection refused"));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{rate_limit::RateLimiter, report::VerifyResult, verify, CliArgs, Prerequisites};
+use ethers::{
+    abi::{decode, ParamType, Token},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest},
+    utils::hex,
+};
+use eyre::Result;
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+/// `facets()` of the EIP-2535 diamond loupe, returns `(address facetAddress, bytes4[] functionSelectors)[]`
+const FACETS_SELECTOR: [u8; 4] = [0x7a, 0x0e, 0xd6, 0x27];
+
+/**
+ * A facet of a diamond with the selectors the diamond routes to it
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facet {
+    pub address: Address,
+    pub selectors: Vec<[u8; 4]>,
+}
+
+/**
+ * Where the source of a facet is, like the arguments of a single verification
+ */
+#[derive(Debug, Clone, Deserialize)]
+pub struct FacetSource {
+    pub git: String,
+    pub commit: Option<String>,
+    pub contract_name: String,
+    /// The transaction that deployed the facet
+    pub transaction: String,
+}
+
+/**
+ * The `--facets-manifest` file, a `[facets.<address>]` table per facet
+ */
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FacetsManifest {
+    #[serde(default)]
+    pub facets: HashMap<String, FacetSource>,
+}
+
+impl FacetsManifest {
+    pub fn load(path: &Path) -> Result<FacetsManifest> {
+        let manifest = std::fs::read_to_string(path)
+            .map_err(|err| eyre::eyre!("Could not read facets manifest {}: {}", path.display(), err))?;
+
+        Ok(toml::from_str(&manifest)?)
+    }
+
+    /**
+     * The addresses may be written with any casing
+     */
+    pub fn source(&self, address: Address) -> Option<&FacetSource> {
+        self.facets
+            .iter()
+            .find(|(key, _)| key.parse::<Address>().ok() == Some(address))
+            .map(|(_, source)| source)
+    }
+}
+
+/**
+ * The outcome for one facet: `result` if it was verified, `error` if it is not in the manifest or the verification failed
+ */
+#[derive(Debug, Clone)]
+pub struct FacetVerifyResult {
+    pub facet: Facet,
+    pub result: Option<VerifyResult>,
+    pub error: Option<String>,
+}
+
+impl FacetVerifyResult {
+    pub fn matched(&self) -> bool {
+        self.result.as_ref().is_some_and(|result| result.matched)
+    }
+}
+
+impl std::fmt::Display for FacetVerifyResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let selectors: Vec<String> = self.facet.selectors.iter().map(|selector| format!("0x{}", hex::encode(selector))).collect();
+        write!(f, "{:?} ({} selectors: {}): ", self.facet.address, selectors.len(), selectors.join(", "))?;
+
+        match (self.result.as_ref(), self.error.as_ref()) {
+            (_, Some(error)) => write!(f, "{}", error),
+            (Some(result), None) => write!(f, "{} {}", result.contract_name, result.match_kind),
+            (None, None) => write!(f, "not verified"),
+        }
+    }
+}
+
+/**
+ * Reads the facets of the diamond with its loupe
+ */
+pub async fn read_facets<M: Middleware>(provider: &M, diamond: Address) -> Result<Vec<Facet>> {
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(diamond)
+        .data(FACETS_SELECTOR.to_vec())
+        .into();
+    let output = provider
+        .call(&tx, None)
+        .await
+        .map_err(|err| eyre::eyre!("Could not call facets() on {:?}: {}", diamond, err))?;
+
+    let facet_type = ParamType::Tuple(vec![ParamType::Address, ParamType::Array(Box::new(ParamType::FixedBytes(4)))]);
+    let tokens = decode(&[ParamType::Array(Box::new(facet_type))], &output)
+        .map_err(|err| eyre::eyre!("facets() of {:?} did not return a list of facets: {}", diamond, err))?;
+
+    let facets = tokens.into_iter().next().and_then(Token::into_array).unwrap_or_default();
+    Ok(facets.into_iter().filter_map(parse_facet).collect())
+}
+
+fn parse_facet(token: Token) -> Option<Facet> {
+    let mut fields = token.into_tuple()?.into_iter();
+    let address = fields.next()?.into_address()?;
+    let selectors = fields
+        .next()?
+        .into_array()?
+        .into_iter()
+        .filter_map(|selector| selector.into_fixed_bytes()?.try_into().ok())
+        .collect();
+
+    Some(Facet { address, selectors })
+}
+
+/**
+ * Verifies every facet of a diamond against the source the manifest lists for it, with the other arguments of this run
+ */
+pub(crate) struct DiamondFacetVerifier<'a> {
+    prerequisites: &'a Prerequisites,
+    cli_args: CliArgs,
+    /// Shared with the rest of the run
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<'a> DiamondFacetVerifier<'a> {
+    pub(crate) fn new(prerequisites: &'a Prerequisites, cli_args: CliArgs, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        DiamondFacetVerifier { prerequisites, cli_args, rate_limiter }
+    }
+
+    pub(crate) async fn verify_all<M: Middleware>(&self, provider: &M, diamond_address: Address, manifest: &FacetsManifest) -> Result<Vec<FacetVerifyResult>> {
+        let mut results = Vec::new();
+
+        for facet in read_facets(provider, diamond_address).await? {
+            let source = match manifest.source(facet.address) {
+                Some(source) => source,
+                None => {
+                    results.push(FacetVerifyResult { facet, result: None, error: Some("not in the facets manifest".to_string()) });
+                    continue;
+                }
+            };
+
+            let mut facet_args = self.cli_args.clone();
+            facet_args.contract_address = Some(format!("{:?}", facet.address));
+            facet_args.transaction = Some(source.transaction.clone());
+            facet_args.git = Some(source.git.clone());
+            facet_args.commit = source.commit.clone();
+            facet_args.contract_name = Some(source.contract_name.clone());
+            facet_args.contract_path = None;
+            facet_args.check_diamond_facets = false;
+
+            // One facet that can't be built shouldn't hide the results of the others
+            match verify(self.prerequisites, facet_args, self.rate_limiter.clone()).await {
+                Ok(result) => results.push(FacetVerifyResult { facet, result: Some(result), error: None }),
+                Err(err) => results.push(FacetVerifyResult { facet, result: None, error: Some(err.to_string()) }),
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FacetsManifest;
+    use ethers::types::Address;
+
+    #[test]
+    fn finds_facets_by_address() {
+        let manifest: FacetsManifest = toml::from_str(
+            r#"
+            [facets.0xABCDEFabcdefABCDEFabcdefABCDEFabcdefABCD]
+            git = "https://github.com/org/diamond"
+            contract_name = "OwnershipFacet"
+            transaction = "0x01"
+            "#,
+        )
+        .unwrap();
+
+        let facet = "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd".parse::<Address>().unwrap();
+        assert_eq!(manifest.source(facet).map(|source| source.contract_name.as_str()), Some("OwnershipFacet"));
+        assert!(manifest.source(Address::zero()).is_none());
+    }
+}
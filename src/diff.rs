@@ -0,0 +1,409 @@
+use crate::{
+    metadata::ContractMetadata,
+    opcodes::{disassemble, Instruction, CODECOPY, PUSH0, PUSH32, RETURN},
+};
+
+/// Edit distance after which two bytecodes are considered too different to diff
+const MAX_EDIT_DISTANCE: usize = 5000;
+
+/**
+ * A single line of an opcode diff
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffOp {
+    Equal(Instruction),
+    Delete(Instruction),
+    Insert(Instruction),
+}
+
+/**
+ * Instructions are the same when their opcode and push data are, offsets are allowed to shift
+ */
+fn same_instruction(a: &Instruction, b: &Instruction) -> bool {
+    a.opcode == b.opcode && a.push_data == b.push_data
+}
+
+/**
+ * One step of an edit script, holding the indexes of the elements it refers to
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// Index in `a` and in `b`
+    Equal(usize, usize),
+    /// Index in `a`
+    Delete(usize),
+    /// Index in `b`
+    Insert(usize),
+}
+
+/**
+ * Computes the shortest edit script from `a` to `b` using Myers' diff algorithm, `None` if it takes more than
+ * `max_edit_distance` deletions and insertions
+ */
+pub fn edit_script<T>(a: &[T], b: &[T], same: impl Fn(&T, &T) -> bool, max_edit_distance: usize) -> Option<Vec<Edit>> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (a.len() + b.len()).min(max_edit_distance) as isize;
+
+    // `v[offset + k]` is the furthest `x` reached on diagonal `k`
+    let offset = max + 1;
+    let mut v = vec![0isize; (2 * offset + 1) as usize];
+    let mut history: Vec<Vec<isize>> = Vec::new();
+    let mut found = false;
+
+    'search: for d in 0..=max {
+        // Only keep the diagonals the backtracking needs for this round
+        history.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+
+        let mut k = -d;
+        while k <= d {
+            let i = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) { v[i + 1] } else { v[i - 1] + 1 };
+            let mut y = x - k;
+
+            while x < n && y < m && same(&a[x as usize], &b[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+
+            v[i] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+
+            k += 2;
+        }
+    }
+
+    if !found {
+        return None;
+    }
+
+    // Walk back from the end to the start to find the edits
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, stored) in history.iter().enumerate().rev() {
+        let d = d as isize;
+        let get = |k: isize| stored[(k + d + 1) as usize];
+
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = get(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal((x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert((y - 1) as usize));
+            } else {
+                edits.push(Edit::Delete((x - 1) as usize));
+            }
+        }
+
+        x = prev_x;
+        y = prev_y;
+    }
+
+    edits.reverse();
+    Some(edits)
+}
+
+/**
+ * Diffs the instructions of two bytecodes, see `edit_script`
+ */
+pub fn diff_opcodes(a: &[Instruction], b: &[Instruction]) -> Vec<DiffOp> {
+    match edit_script(a, b, same_instruction, MAX_EDIT_DISTANCE) {
+        Some(edits) => edits
+            .into_iter()
+            .map(|edit| match edit {
+                Edit::Equal(x, _) => DiffOp::Equal(a[x].clone()),
+                Edit::Delete(x) => DiffOp::Delete(a[x].clone()),
+                Edit::Insert(y) => DiffOp::Insert(b[y].clone()),
+            })
+            .collect(),
+        // Too different to produce a useful diff, everything is replaced
+        None => a
+            .iter()
+            .cloned()
+            .map(DiffOp::Delete)
+            .chain(b.iter().cloned().map(DiffOp::Insert))
+            .collect(),
+    }
+}
+
+/**
+ * Formats the diff like `diff -u`, only showing `context` unchanged instructions around each change
+ */
+pub fn format_opcode_diff(ops: &[DiffOp], from: &str, to: &str, context: usize) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", from, to);
+
+    // Mark every line that is close enough to a change to be shown
+    let mut visible = vec![false; ops.len()];
+    for (index, op) in ops.iter().enumerate() {
+        if !matches!(op, DiffOp::Equal(_)) {
+            let start = index.saturating_sub(context);
+            let end = (index + context + 1).min(ops.len());
+            visible[start..end].iter_mut().for_each(|line| *line = true);
+        }
+    }
+
+    let mut previous_visible = false;
+    for (index, op) in ops.iter().enumerate() {
+        if !visible[index] {
+            previous_visible = false;
+            continue;
+        }
+
+        if !previous_visible {
+            output.push_str("@@\n");
+        }
+        previous_visible = true;
+
+        let line = match op {
+            DiffOp::Equal(instruction) => format!(" {:06x} {}\n", instruction.offset, instruction),
+            DiffOp::Delete(instruction) => format!("-{:06x} {}\n", instruction.offset, instruction),
+            DiffOp::Insert(instruction) => format!("+{:06x} {}\n", instruction.offset, instruction),
+        };
+        output.push_str(&line);
+    }
+
+    output
+}
+
+/**
+ * Byte level statistics about the differences between two bytecodes
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BytecodeDiffStats {
+    /// Bytes that were replaced, inserted or deleted, a replaced byte only counts once
+    pub differing_bytes: usize,
+    /// `differing_bytes` as a percentage of the longest bytecode
+    pub difference_percentage: f64,
+    /// Length of the longest common subsequence of both bytecodes
+    pub lcs_length: usize,
+    /// Differing bytes in the constructor part of the init code
+    pub constructor_changes: usize,
+    /// Differing bytes in the deployed code and constructor arguments
+    pub body_changes: usize,
+    /// Differing bytes in the CBOR metadata
+    pub metadata_changes: usize,
+}
+
+impl BytecodeDiffStats {
+    /**
+     * Counts the changes of the shortest edit script from `a` to `b`, sections are determined using `a`. Bytecodes that
+     * are too different to diff count as entirely replaced.
+     */
+    pub fn compute(a: &[u8], b: &[u8]) -> BytecodeDiffStats {
+        let edits = edit_script(a, b, |x, y| x == y, MAX_EDIT_DISTANCE)
+            .unwrap_or_else(|| (0..a.len()).map(Edit::Delete).chain((0..b.len()).map(Edit::Insert)).collect());
+
+        let constructor_end = runtime_start(a);
+        let metadata = ContractMetadata::find(a).map(|(range, _)| range);
+
+        let mut stats = BytecodeDiffStats::default();
+        let mut position = 0;
+        let mut hunk: Option<(usize, usize, usize)> = None;
+        for edit in edits.iter().map(Some).chain(std::iter::once(None)) {
+            // A run of deletions and insertions replaces one part of `a` with one of `b`
+            match edit {
+                Some(Edit::Delete(x)) => {
+                    let (start, deleted, inserted) = hunk.unwrap_or((*x, 0, 0));
+                    hunk = Some((start, deleted + 1, inserted));
+                    position = x + 1;
+                    continue;
+                }
+                Some(Edit::Insert(_)) => {
+                    let (start, deleted, inserted) = hunk.unwrap_or((position, 0, 0));
+                    hunk = Some((start, deleted, inserted + 1));
+                    continue;
+                }
+                Some(Edit::Equal(x, _)) => {
+                    stats.lcs_length += 1;
+                    position = x + 1;
+                }
+                None => {}
+            }
+
+            if let Some((start, deleted, inserted)) = hunk.take() {
+                let changes = deleted.max(inserted);
+                stats.differing_bytes += changes;
+                match &metadata {
+                    Some(range) if range.contains(&start) => stats.metadata_changes += changes,
+                    _ if start < constructor_end => stats.constructor_changes += changes,
+                    _ => stats.body_changes += changes,
+                }
+            }
+        }
+
+        let longest = a.len().max(b.len());
+        if longest > 0 {
+            stats.difference_percentage = stats.differing_bytes as f64 / longest as f64 * 100.0;
+        }
+
+        stats
+    }
+
+    /**
+     * The share of bytes the edit script keeps, `1.0` means the bytecodes are identical
+     */
+    pub fn similarity(&self) -> f64 {
+        1.0 - self.difference_percentage / 100.0
+    }
+}
+
+/**
+ * Where the deployed code starts in the init code, `0` if it can not be found, meaning there is no constructor part.
+ *
+ * The constructor ends by copying the deployed code to memory and returning it, `PUSH <offset> PUSH <0> CODECOPY` followed
+ * by a `RETURN`, which solc emits with and without the IR pipeline. Code that does not do that falls back to the second
+ * occurrence of the `6080604052` memory setup, which init code and deployed code both start with.
+ */
+fn runtime_start(init_code: &[u8]) -> usize {
+    const PREAMBLE: [u8; 5] = [0x60, 0x80, 0x60, 0x40, 0x52];
+
+    let instructions = disassemble(init_code);
+    let push_value = |instruction: &Instruction| -> Option<usize> {
+        if !(PUSH0..=PUSH32).contains(&instruction.opcode) || instruction.push_data.len() > 8 {
+            return None;
+        }
+        Some(instruction.push_data.iter().fold(0usize, |value, byte| (value << 8) | *byte as usize))
+    };
+
+    let copied = instructions.windows(3).enumerate().find_map(|(index, window)| {
+        let returns = instructions[index + 3..].iter().take(3).any(|instruction| instruction.opcode == RETURN);
+        if window[2].opcode != CODECOPY || push_value(&window[1]) != Some(0) || !returns {
+            return None;
+        }
+        push_value(&window[0]).filter(|offset| *offset > 0 && *offset < init_code.len())
+    });
+    if let Some(offset) = copied {
+        return offset;
+    }
+
+    if !init_code.starts_with(&PREAMBLE) {
+        return 0;
+    }
+
+    init_code
+        .windows(PREAMBLE.len())
+        .skip(1)
+        .position(|window| window == PREAMBLE)
+        .map(|position| position + 1)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_opcodes, edit_script, runtime_start, BytecodeDiffStats, DiffOp, Edit};
+    use crate::opcodes::disassemble;
+
+    /// `PUSH1 0x80 PUSH1 0x40 MSTORE CALLVALUE`
+    const CODE: [u8; 6] = [0x60, 0x80, 0x60, 0x40, 0x52, 0x34];
+
+    fn kinds(ops: &[DiffOp]) -> String {
+        ops.iter()
+            .map(|op| match op {
+                DiffOp::Equal(_) => '=',
+                DiffOp::Delete(_) => '-',
+                DiffOp::Insert(_) => '+',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diffs_empty_bytecode() {
+        assert!(diff_opcodes(&[], &[]).is_empty());
+        assert_eq!(kinds(&diff_opcodes(&[], &disassemble(&CODE))), "++++");
+        assert_eq!(kinds(&diff_opcodes(&disassemble(&CODE), &[])), "----");
+    }
+
+    #[test]
+    fn diffs_identical_bytecode() {
+        assert_eq!(kinds(&diff_opcodes(&disassemble(&CODE), &disassemble(&CODE))), "====");
+    }
+
+    #[test]
+    fn diffs_single_insert_and_delete() {
+        // CALLER after MSTORE, every following offset shifts by one
+        let inserted = [0x60, 0x80, 0x60, 0x40, 0x52, 0x33, 0x34];
+        let ops = diff_opcodes(&disassemble(&CODE), &disassemble(&inserted));
+        assert_eq!(kinds(&ops), "===+=");
+        assert_eq!(ops[3], DiffOp::Insert(disassemble(&inserted)[3].clone()));
+
+        let ops = diff_opcodes(&disassemble(&inserted), &disassemble(&CODE));
+        assert_eq!(kinds(&ops), "===-=");
+    }
+
+    #[test]
+    fn compares_push_data() {
+        // PUSH1 0x81 instead of PUSH1 0x80
+        let changed = [0x60, 0x81, 0x60, 0x40, 0x52, 0x34];
+        assert_eq!(kinds(&diff_opcodes(&disassemble(&CODE), &disassemble(&changed))), "-+===");
+    }
+
+    #[test]
+    fn gives_up_after_max_edit_distance() {
+        let same = |a: &u8, b: &u8| a == b;
+        assert_eq!(edit_script(&[0u8; 3], &[1u8; 3], same, 5), None);
+        assert_eq!(edit_script(&[0u8; 3], &[1u8; 3], same, 6).map(|edits| edits.len()), Some(6));
+        assert_eq!(edit_script(&[0u8, 1], &[1u8], same, 1), Some(vec![Edit::Delete(0), Edit::Equal(1, 0)]));
+    }
+
+    /// `PUSH2 0x0005 DUP1 PUSH2 <offset> PUSH1 0x00 CODECOPY PUSH1 0x00 RETURN INVALID` after the memory setup
+    fn constructor(offset: u8) -> Vec<u8> {
+        vec![0x60, 0x80, 0x60, 0x40, 0x52, 0x61, 0x00, 0x05, 0x80, 0x61, 0x00, offset, 0x60, 0x00, 0x39, 0x60, 0x00, 0xf3, 0xfe]
+    }
+
+    #[test]
+    fn finds_the_runtime_code() {
+        let init_code = [constructor(19), CODE.to_vec()].concat();
+        assert_eq!(runtime_start(&init_code), 19);
+
+        // Without the memory setup, `PUSH2 0x0002 DUP1 PUSH2 0x000c PUSH0 CODECOPY PUSH0 RETURN INVALID PUSH1 0x01`
+        let init_code = [0x61, 0x00, 0x02, 0x80, 0x61, 0x00, 0x0c, 0x5f, 0x39, 0x5f, 0xf3, 0xfe, 0x60, 0x01];
+        assert_eq!(runtime_start(&init_code), 12);
+
+        // Deployed code has no constructor
+        assert_eq!(runtime_start(&CODE), 0);
+    }
+
+    #[test]
+    fn counts_no_changes_for_identical_bytecode() {
+        let init_code = [constructor(19), CODE.to_vec()].concat();
+        let stats = BytecodeDiffStats::compute(&init_code, &init_code);
+        assert_eq!(stats.differing_bytes, 0);
+        assert_eq!(stats.lcs_length, init_code.len());
+        assert_eq!(stats.difference_percentage, 0.0);
+    }
+
+    #[test]
+    fn counts_shifted_bytes_once() {
+        // A byte inserted at the start of the deployed code shifts everything after it
+        let a = [constructor(19), CODE.to_vec()].concat();
+        let b = [constructor(19), vec![0x5b], CODE.to_vec()].concat();
+
+        let stats = BytecodeDiffStats::compute(&a, &b);
+        assert_eq!(stats.differing_bytes, 1);
+        assert_eq!(stats.lcs_length, a.len());
+        assert_eq!((stats.constructor_changes, stats.body_changes, stats.metadata_changes), (0, 1, 0));
+    }
+
+    #[test]
+    fn counts_changes_per_section() {
+        // Another offset in the constructor and another push value in the deployed code
+        let a = [constructor(19), CODE.to_vec()].concat();
+        let mut b = [constructor(20), vec![0x00], CODE.to_vec()].concat();
+        b[21] = 0x81;
+
+        let stats = BytecodeDiffStats::compute(&a, &b);
+        assert_eq!(stats.differing_bytes, 3);
+        assert_eq!((stats.constructor_changes, stats.body_changes, stats.metadata_changes), (1, 2, 0));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::opcodes::disassemble;
+use ethers::{
+    abi::{self, ParamType, Token},
+    providers::{Middleware, MiddlewareError},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use eyre::Result;
+
+/// `UserOperation` of the v0.6 EntryPoint
+const USER_OPERATION: &str = "(address,uint256,bytes,bytes,uint256,uint256,uint256,uint256,uint256,bytes,bytes)";
+
+/// Selector of `Error(string)`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/**
+ * What happened when calling a validation function with an invalid `UserOperation`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallOutcome {
+    /// The invalid operation was accepted, which a correct implementation never does
+    Succeeded,
+    /// Rejected, with the revert reason if it could be decoded
+    Reverted { reason: Option<String> },
+    /// Reverted without any data, usually because the function does not exist
+    RevertedWithoutData,
+}
+
+impl std::fmt::Display for CallOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallOutcome::Succeeded => write!(f, "succeeded"),
+            CallOutcome::Reverted { reason: Some(reason) } => write!(f, "reverted with \"{}\"", reason),
+            CallOutcome::Reverted { reason: None } => write!(f, "reverted"),
+            CallOutcome::RevertedWithoutData => write!(f, "reverted without data"),
+        }
+    }
+}
+
+/**
+ * ERC-4337 validation functions of a contract, for smart accounts (`validateUserOp`) and paymasters (`validatePaymasterUserOp`)
+ */
+#[derive(Debug, Clone)]
+pub struct Erc4337Report {
+    /// The selector of `validateUserOp` is used by the function dispatcher
+    pub has_validate_user_op: bool,
+    pub validate_user_op: CallOutcome,
+    /// The selector of `validatePaymasterUserOp` is used by the function dispatcher
+    pub has_validate_paymaster_user_op: bool,
+    pub validate_paymaster_user_op: CallOutcome,
+}
+
+impl Erc4337Report {
+    pub fn is_account(&self) -> bool {
+        self.has_validate_user_op
+    }
+
+    pub fn is_paymaster(&self) -> bool {
+        self.has_validate_paymaster_user_op
+    }
+}
+
+/**
+ * Looks for the validation functions in the deployed code and calls them with an invalid `UserOperation`,
+ * which has to revert since the call does not come from the EntryPoint and the operation is not signed
+ */
+pub async fn check_erc4337_compliance<M: Middleware>(provider: &M, address: Address) -> Result<Erc4337Report> {
+    let code = provider
+        .get_code(address, None)
+        .await
+        .map_err(|err| eyre::eyre!("Could not fetch the code of {:?}: {}", address, err))?;
+
+    let validate_user_op = id(format!("validateUserOp({},bytes32,uint256)", USER_OPERATION));
+    let validate_paymaster_user_op = id(format!("validatePaymasterUserOp({},bytes32,uint256)", USER_OPERATION));
+
+    Ok(Erc4337Report {
+        has_validate_user_op: dispatches_selector(&code, validate_user_op),
+        validate_user_op: call_with_invalid_user_op(provider, address, validate_user_op).await?,
+        has_validate_paymaster_user_op: dispatches_selector(&code, validate_paymaster_user_op),
+        validate_paymaster_user_op: call_with_invalid_user_op(provider, address, validate_paymaster_user_op).await?,
+    })
+}
+
+/**
+ * Function dispatchers compare the selector of the calldata with a `PUSH4` of every public function
+ */
+fn dispatches_selector(code: &[u8], selector: [u8; 4]) -> bool {
+    disassemble(code)
+        .iter()
+        .any(|instruction| instruction.push_data == selector)
+}
+
+async fn call_with_invalid_user_op<M: Middleware>(provider: &M, address: Address, selector: [u8; 4]) -> Result<CallOutcome> {
+    // Unsigned operation without any gas, from the contract itself
+    let user_op = Token::Tuple(vec![
+        Token::Address(address),
+        Token::Uint(U256::zero()),
+        Token::Bytes(vec![]),
+        Token::Bytes(vec![]),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Bytes(vec![]),
+        Token::Bytes(vec![]),
+    ]);
+    let mut calldata = selector.to_vec();
+    calldata.extend(abi::encode(&[user_op, Token::FixedBytes(vec![0; 32]), Token::Uint(U256::zero())]));
+
+    call_outcome(provider, address, calldata).await
+}
+
+/**
+ * Calls the contract with `eth_call` at the latest block and reports whether the call reverted
+ */
+pub async fn call_outcome<M: Middleware>(provider: &M, address: Address, calldata: Vec<u8>) -> Result<CallOutcome> {
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(address)
+        .data(calldata)
+        .into();
+
+    match provider.call(&tx, None).await {
+        Ok(_) => Ok(CallOutcome::Succeeded),
+        Err(err) => match err.as_error_response() {
+            Some(response) => Ok(match response.as_revert_data() {
+                Some(data) if !data.is_empty() => CallOutcome::Reverted { reason: decode_revert_reason(&data) },
+                Some(_) => CallOutcome::RevertedWithoutData,
+                None => CallOutcome::Reverted { reason: Some(response.message.clone()) },
+            }),
+            None => Err(eyre::eyre!("Could not call {:?}: {}", address, err)),
+        },
+    }
+}
+
+/**
+ * Decodes the message of an `Error(string)` revert
+ */
+fn decode_revert_reason(data: &Bytes) -> Option<String> {
+    if data.len() < 4 || data[..4] != ERROR_SELECTOR {
+        return None;
+    }
+
+    match abi::decode(&[ParamType::String], &data[4..]).ok()?.pop()? {
+        Token::String(reason) => Some(reason),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{call_outcome, dispatches_selector, CallOutcome, ERROR_SELECTOR};
+    use crate::mock_provider::MockProvider;
+    use ethers::{
+        abi::{self, Token},
+        types::{Address, Bytes},
+        utils::hex,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn finds_selectors_of_the_dispatcher() {
+        // `PUSH4 0x3a871cdd EQ`, `validateUserOp` of the v0.6 EntryPoint
+        let code = hex::decode("633a871cdd14").unwrap();
+
+        assert!(dispatches_selector(&code, [0x3a, 0x87, 0x1c, 0xdd]));
+        assert!(!dispatches_selector(&code, [0xf4, 0x65, 0xc7, 0x7e]));
+    }
+
+    #[tokio::test]
+    async fn accepted_operations_succeed() {
+        let mock = MockProvider::default().with_rpc_response(Bytes::from(vec![0; 32]));
+
+        assert_eq!(call_outcome(&mock, Address::repeat_byte(1), vec![]).await.unwrap(), CallOutcome::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn decodes_the_revert_reason() {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend(abi::encode(&[Token::String("AA24 signature error".to_string())]));
+        let mock = MockProvider::default().with_rpc_error("execution reverted", Some(json!(Bytes::from(data))));
+
+        assert_eq!(
+            call_outcome(&mock, Address::repeat_byte(1), vec![]).await.unwrap(),
+            CallOutcome::Reverted { reason: Some("AA24 signature error".to_string()) }
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_functions_revert_without_data() {
+        let mock = MockProvider::default().with_rpc_error("execution reverted", Some(json!("0x")));
+
+        assert_eq!(call_outcome(&mock, Address::repeat_byte(1), vec![]).await.unwrap(), CallOutcome::RevertedWithoutData);
+    }
+}
@@ -0,0 +1,44 @@
+use std::{fmt, process::Command};
+
+/**
+ * Errors of the verification that carry more context than a message
+ */
+#[derive(Debug)]
+pub enum VerifyError {
+    /// An external command (git, forge, npm, ...) exited with a non-zero code
+    SubprocessError {
+        command: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+}
+
+impl VerifyError {
+    /**
+     * Describes the command as it would be typed in a shell
+     */
+    pub fn format_command(command: &Command) -> String {
+        let mut parts = vec![command.get_program().to_string_lossy().to_string()];
+        parts.extend(command.get_args().map(|arg| arg.to_string_lossy().to_string()));
+        shell_words::join(parts)
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::SubprocessError { command, exit_code, stderr } => {
+                match exit_code {
+                    Some(code) => write!(f, "`{}` failed with exit code {}", command, code)?,
+                    None => write!(f, "`{}` was terminated by a signal", command)?,
+                }
+                if !stderr.trim().is_empty() {
+                    write!(f, ":\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
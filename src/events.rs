@@ -0,0 +1,56 @@
+use ethers::{
+    abi::Event as AbiEvent,
+    types::{Log, H256},
+};
+
+/**
+ * The result of comparing the events emitted during deployment with the events in the compiled ABI
+ */
+#[derive(Debug, Default)]
+pub struct EventMatchReport {
+    /// Events from the ABI that were emitted
+    pub matched: Vec<String>,
+    /// `topics[0]` of emitted logs that don't match any event in the ABI
+    pub unknown_topics: Vec<H256>,
+    /// Events from the ABI that were never emitted
+    pub not_emitted: Vec<String>,
+}
+
+/**
+ * Matches the `topics[0]` of the logs against the signatures of the ABI events, anonymous events have no signature topic and are ignored
+ */
+pub fn match_event_signatures(logs: &[Log], abi_events: &[AbiEvent]) -> EventMatchReport {
+    let mut report = EventMatchReport::default();
+
+    let signatures: Vec<(H256, String)> = abi_events
+        .iter()
+        .filter(|event| !event.anonymous)
+        .map(|event| (event.signature(), event_signature(event)))
+        .collect();
+
+    let emitted: Vec<H256> = logs.iter().filter_map(|log| log.topics.first().copied()).collect();
+
+    for topic in emitted.iter() {
+        if !signatures.iter().any(|(signature, _)| signature == topic) && !report.unknown_topics.contains(topic) {
+            report.unknown_topics.push(*topic);
+        }
+    }
+
+    for (signature, name) in signatures {
+        if emitted.contains(&signature) {
+            report.matched.push(name);
+        } else {
+            report.not_emitted.push(name);
+        }
+    }
+
+    report
+}
+
+/**
+ * `Name(type1,type2)`, the text that is hashed to get `topics[0]`
+ */
+pub fn event_signature(event: &AbiEvent) -> String {
+    let types: Vec<String> = event.inputs.iter().map(|input| input.kind.to_string()).collect();
+    format!("{}({})", event.name, types.join(","))
+}
@@ -0,0 +1,44 @@
+use ethers::types::Address;
+
+/// The block explorer of every chain we know one for, by chain id
+const EXPLORERS: [(u64, &str); 14] = [
+    (1, "https://etherscan.io"),
+    (10, "https://optimistic.etherscan.io"),
+    (56, "https://bscscan.com"),
+    (100, "https://gnosisscan.io"),
+    (137, "https://polygonscan.com"),
+    (250, "https://ftmscan.com"),
+    (324, "https://explorer.zksync.io"),
+    (1101, "https://zkevm.polygonscan.com"),
+    (8453, "https://basescan.org"),
+    (42161, "https://arbiscan.io"),
+    (43114, "https://snowtrace.io"),
+    (59144, "https://lineascan.build"),
+    (11155111, "https://sepolia.etherscan.io"),
+    (17000, "https://holesky.etherscan.io"),
+];
+
+/**
+ * The page of the contract on the block explorer of the chain, `None` for chains without a known explorer
+ */
+pub fn explorer_url(chain_id: u64, address: Address) -> Option<String> {
+    EXPLORERS
+        .iter()
+        .find(|(id, _)| *id == chain_id)
+        .map(|(_, url)| format!("{}/address/{:?}", url, address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::explorer_url;
+    use ethers::types::Address;
+
+    #[test]
+    fn builds_address_urls() {
+        assert_eq!(
+            explorer_url(137, Address::zero()).as_deref(),
+            Some("https://polygonscan.com/address/0x0000000000000000000000000000000000000000")
+        );
+        assert_eq!(explorer_url(31337, Address::zero()), None);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::analyzer::BytecodeAnalyzer;
+use ethers::utils::hex;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+/**
+ * The sha256 hash of a bytecode without its metadata (and constructor arguments), which only changes when the code does.
+ * Fingerprints of trusted builds can be stored and compared against future deployments without verifying them again.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BytecodeFingerprint([u8; 32]);
+
+impl BytecodeFingerprint {
+    pub fn of(bytecode: &[u8]) -> BytecodeFingerprint {
+        let stripped = BytecodeAnalyzer::new(bytecode).strip_metadata().to_vec();
+        BytecodeFingerprint(Sha256::digest(stripped).into())
+    }
+}
+
+impl std::fmt::Display for BytecodeFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl Serialize for BytecodeFingerprint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
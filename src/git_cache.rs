@@ -0,0 +1,117 @@
+use crate::interrupt::run_command;
+use eyre::Result;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/**
+ * Keeps a bare clone per repository URL in the cache directory and checks out commits as worktrees,
+ * so verifying another commit of the same repository only fetches what is new
+ */
+pub struct GitWorktreeCache {
+    git: PathBuf,
+    root: PathBuf,
+    /// `GIT_SSH_COMMAND` of the clone and fetch
+    ssh_command: Option<String>,
+}
+
+impl GitWorktreeCache {
+    /**
+     * Uses `$XDG_CACHE_HOME/bytematch/repositories`, or `~/.cache/bytematch/repositories` when it is not set
+     */
+    pub fn new(git: &Path) -> Result<Self> {
+        let cache_home = match env::var("XDG_CACHE_HOME") {
+            Ok(cache_home) if !cache_home.is_empty() => PathBuf::from(cache_home),
+            _ => {
+                let home = env::var("HOME").map_err(|_| eyre::eyre!("Could not find the home directory for the git cache"))?;
+                PathBuf::from(home).join(".cache")
+            }
+        };
+
+        Ok(GitWorktreeCache {
+            git: git.to_path_buf(),
+            root: cache_home.join("bytematch").join("repositories"),
+            ssh_command: None,
+        })
+    }
+
+    pub fn with_ssh_command(mut self, ssh_command: Option<String>) -> Self {
+        self.ssh_command = ssh_command;
+        self
+    }
+
+    /**
+     * Location of the bare clone, the URL is turned into a readable folder name
+     */
+    fn repository_path(&self, git_url: &str) -> PathBuf {
+        let name: String = git_url
+            .trim_end_matches(".git")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+
+        self.root.join(format!("{}.git", name))
+    }
+
+    /**
+     * Clones the repository if it is not cached yet, otherwise fetches its new branches and tags
+     */
+    fn update(&self, git_url: &str) -> Result<PathBuf> {
+        let repository = self.repository_path(git_url);
+
+        let output = if repository.exists() {
+            run_command(Command::new(&self.git)
+                .args(["fetch", "--prune", "origin", "+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"])
+                .envs(self.ssh_command.as_deref().map(|command| ("GIT_SSH_COMMAND", command)))
+                .current_dir(&repository))?
+        } else {
+            std::fs::create_dir_all(&self.root)?;
+            run_command(Command::new(&self.git)
+                .args(["clone", "--bare", git_url])
+                .arg(&repository)
+                .envs(self.ssh_command.as_deref().map(|command| ("GIT_SSH_COMMAND", command))))?
+        };
+
+        if !output.status.success() {
+            return Err(eyre::eyre!("Could not update the cached clone of {}: {}", git_url, String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(repository)
+    }
+
+    /**
+     * Checks out the revision (a tag, branch, commit or `HEAD`) of the repository as a worktree at `destination`
+     */
+    pub fn add_worktree(&self, git_url: &str, revision: &str, destination: &Path) -> Result<()> {
+        let repository = self.update(git_url)?;
+
+        // Forget worktrees whose folder was removed since the last run
+        run_command(Command::new(&self.git)
+            .args(["worktree", "prune"])
+            .current_dir(&repository))?;
+
+        // Commits that are not on any branch are not part of the clone
+        let exists = run_command(Command::new(&self.git)
+            .args(["cat-file", "-e", &format!("{}^{{commit}}", revision)])
+            .current_dir(&repository))?;
+        if !exists.status.success() {
+            run_command(Command::new(&self.git)
+                .args(["fetch", "origin", revision])
+                .envs(self.ssh_command.as_deref().map(|command| ("GIT_SSH_COMMAND", command)))
+                .current_dir(&repository))?;
+        }
+
+        let output = run_command(Command::new(&self.git)
+            .args(["worktree", "add", "--force", "--detach"])
+            .arg(destination)
+            .arg(revision)
+            .current_dir(&repository))?;
+        if !output.status.success() {
+            return Err(eyre::eyre!("{} does not exist in {}: {}", revision, git_url, String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+
+/// Hosts that can be given without a scheme, e.g. `github.com/user/repo`
+const SHORTHAND_HOSTS: [&str; 3] = ["github.com", "gitlab.com", "bitbucket.org"];
+
+/**
+ * Why a git url was rejected by `--sanitize-git-url`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitUrlError {
+    Empty,
+    /// The url would be parsed as an option of `git`, e.g. `--upload-pack=...`
+    OptionInjection(String),
+    /// Only `https://` and `git@host:path` urls are allowed, transports like `ext::` run arbitrary commands
+    UnsupportedScheme(String),
+    /// Whitespace and control characters are never part of a valid url
+    InvalidCharacter(String),
+    /// The url has no host or no repository path
+    MissingRepository(String),
+}
+
+impl fmt::Display for GitUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitUrlError::Empty => write!(f, "The git url is empty"),
+            GitUrlError::OptionInjection(url) => write!(f, "The git url '{}' looks like a git option", url),
+            GitUrlError::UnsupportedScheme(url) => write!(f, "The git url '{}' is neither an https:// nor a git@ url", url),
+            GitUrlError::InvalidCharacter(url) => write!(f, "The git url '{}' contains whitespace or control characters", url),
+            GitUrlError::MissingRepository(url) => write!(f, "The git url '{}' does not name a host and repository", url),
+        }
+    }
+}
+
+impl std::error::Error for GitUrlError {}
+
+/**
+ * Validates a git url before it is passed to `git`, and normalizes it so the same repository always has the same url:
+ * `github.com/user/repo` is expanded to `https://github.com/user/repo.git`, trailing slashes are dropped and the url
+ * ends in exactly one `.git`.
+ */
+pub fn sanitize_git_url(input: &str) -> Result<String, GitUrlError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(GitUrlError::Empty);
+    }
+    if input.starts_with('-') || input.contains("--upload-pack") || input.contains("--receive-pack") || input.contains("--config") {
+        return Err(GitUrlError::OptionInjection(input.to_string()));
+    }
+    if input.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(GitUrlError::InvalidCharacter(input.to_string()));
+    }
+    if input.contains("::") {
+        return Err(GitUrlError::UnsupportedScheme(input.to_string()));
+    }
+
+    let (prefix, rest) = if let Some(rest) = input.strip_prefix("https://") {
+        ("https://", rest)
+    } else if let Some(rest) = input.strip_prefix("git@") {
+        ("git@", rest)
+    } else if SHORTHAND_HOSTS.iter().any(|host| input.starts_with(&format!("{}/", host))) {
+        ("https://", input)
+    } else {
+        return Err(GitUrlError::UnsupportedScheme(input.to_string()));
+    };
+
+    // `git@host:path` separates the path with a colon, `https://host/path` with a slash
+    let separator = if prefix == "git@" { ':' } else { '/' };
+    let (host, path) = rest.split_once(separator).ok_or_else(|| GitUrlError::MissingRepository(input.to_string()))?;
+
+    let mut path = path.trim_end_matches('/');
+    while let Some(stripped) = path.strip_suffix(".git") {
+        path = stripped.trim_end_matches('/');
+    }
+    if host.is_empty() || host.starts_with('-') || path.is_empty() {
+        return Err(GitUrlError::MissingRepository(input.to_string()));
+    }
+
+    Ok(format!("{}{}{}{}.git", prefix, host, separator, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize_git_url, GitUrlError};
+
+    #[test]
+    fn normalizes_urls() {
+        assert_eq!(sanitize_git_url("github.com/user/repo").unwrap(), "https://github.com/user/repo.git");
+        assert_eq!(sanitize_git_url("https://github.com/user/repo.git.git/").unwrap(), "https://github.com/user/repo.git");
+        assert_eq!(sanitize_git_url("git@github.com:user/repo").unwrap(), "git@github.com:user/repo.git");
+    }
+
+    #[test]
+    fn rejects_injection() {
+        assert!(matches!(sanitize_git_url("--upload-pack=touch /tmp/pwned"), Err(GitUrlError::OptionInjection(_))));
+        assert!(matches!(sanitize_git_url("ext::sh -c touch% /tmp/pwned"), Err(GitUrlError::InvalidCharacter(_))));
+        assert!(matches!(sanitize_git_url("ext::sh"), Err(GitUrlError::UnsupportedScheme(_))));
+        assert!(matches!(sanitize_git_url("file:///etc"), Err(GitUrlError::UnsupportedScheme(_))));
+        assert_eq!(sanitize_git_url(" "), Err(GitUrlError::Empty));
+    }
+}
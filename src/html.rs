@@ -0,0 +1,136 @@
+use crate::{metadata::ContractMetadata, report::FullVerifyReport};
+
+/**
+ * Self-contained page, the placeholders are replaced by `render_html`
+ */
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>bytematch report</title>
+<style>
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2rem; color: #1f2328; }
+h2 { margin-top: 2rem; }
+.banner { padding: 1rem 1.5rem; border-radius: 6px; font-size: 1.25rem; font-weight: bold; color: #fff; }
+.banner.matched { background: #1a7f37; }
+.banner.mismatched { background: #cf222e; }
+table { border-collapse: collapse; }
+td, th { text-align: left; padding: 0.25rem 1rem 0.25rem 0; vertical-align: top; }
+th { color: #656d76; font-weight: normal; }
+.hex { font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 0.8rem; max-height: 20rem; overflow: auto; border: 1px solid #d0d7de; border-radius: 6px; padding: 0.5rem; word-break: break-all; }
+.hex span.diff { background: #ffebe9; color: #cf222e; }
+.hex.only-diff span:not(.diff) { display: none; }
+pre { background: #f6f8fa; padding: 0.5rem; border-radius: 6px; overflow: auto; }
+</style>
+</head>
+<body>
+<div class="banner {{STATUS_CLASS}}">{{STATUS}}</div>
+
+<h2>Contract</h2>
+<table>
+{{DETAILS}}
+</table>
+
+<h2>Warnings</h2>
+{{WARNINGS}}
+
+<h2>Bytecode</h2>
+<label><input type="checkbox" id="only-diff"> Only show differing bytes</label>
+<h3>Compiled</h3>
+<div class="hex">{{COMPILED}}</div>
+<h3>On-chain</h3>
+<div class="hex">{{TRACED}}</div>
+
+<h2>Metadata</h2>
+<h3>Compiled</h3>
+<pre>{{COMPILED_METADATA}}</pre>
+<h3>On-chain</h3>
+<pre>{{TRACED_METADATA}}</pre>
+
+<script>
+document.getElementById("only-diff").addEventListener("change", function (event) {
+    document.querySelectorAll(".hex").forEach(function (viewer) {
+        viewer.classList.toggle("only-diff", event.target.checked);
+    });
+});
+</script>
+</body>
+</html>
+"#;
+
+/**
+ * Renders the report as a standalone HTML page, no external resources are needed to view it
+ */
+pub fn render_html(report: &FullVerifyReport) -> String {
+    let (status, status_class) = if report.matched {
+        ("Matching contract deployment!", "matched")
+    } else {
+        ("Did not match", "mismatched")
+    };
+
+    let details: Vec<String> = [
+        ("Chain ID", report.chain_id.to_string()),
+        ("Contract address", report.contract_address.clone()),
+        ("Transaction", report.transaction.clone()),
+        ("Git URL", report.git_url.clone()),
+        ("Commit", report.commit.clone().unwrap_or_else(|| "-".to_string())),
+        ("Compiler version", report.compiler_version.clone().unwrap_or_else(|| "-".to_string())),
+        ("Similarity", format!("{:.2}%", report.similarity * 100.0)),
+        ("Timestamp", report.timestamp.to_string()),
+    ]
+    .iter()
+    .map(|(name, value)| format!("<tr><th>{}</th><td>{}</td></tr>", name, escape(value)))
+    .collect();
+
+    let warnings = if report.warnings.is_empty() {
+        "<p>None</p>".to_string()
+    } else {
+        let items: Vec<String> = report.warnings.iter()
+            .map(|warning| format!("<li>{}</li>", escape(warning)))
+            .collect();
+        format!("<ul>{}</ul>", items.join(""))
+    };
+
+    let metadata = |metadata: &Option<ContractMetadata>| {
+        escape(&serde_json::to_string_pretty(metadata).unwrap_or_default())
+    };
+
+    TEMPLATE
+        .replace("{{STATUS_CLASS}}", status_class)
+        .replace("{{STATUS}}", status)
+        .replace("{{DETAILS}}", &details.join("\n"))
+        .replace("{{WARNINGS}}", &warnings)
+        .replace("{{COMPILED}}", &hex_view(&report.compiled_bytecode, &report.traced_bytecode))
+        .replace("{{TRACED}}", &hex_view(&report.traced_bytecode, &report.compiled_bytecode))
+        .replace("{{COMPILED_METADATA}}", &metadata(&report.compiled_metadata))
+        .replace("{{TRACED_METADATA}}", &metadata(&report.traced_metadata))
+}
+
+/**
+ * One span per byte, bytes that differ from the other bytecode at the same position are highlighted
+ */
+fn hex_view(bytecode: &str, other: &str) -> String {
+    let bytecode = bytecode.trim_start_matches("0x").as_bytes();
+    let other = other.trim_start_matches("0x").as_bytes();
+
+    bytecode
+        .chunks(2)
+        .enumerate()
+        .map(|(index, byte)| {
+            let byte = String::from_utf8_lossy(byte);
+            let same = other.get(index * 2..index * 2 + 2).map(|other| other == byte.as_bytes()).unwrap_or(false);
+            if same {
+                format!("<span>{}</span>", byte)
+            } else {
+                format!("<span class=\"diff\">{}</span>", byte)
+            }
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,83 @@
+use eyre::Result;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/**
+ * Where an immutable is inlined into the deployed bytecode
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ImmutableRef {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// The `deployedBytecode.immutableReferences` of a solc artifact, keyed by the AST id of the immutable variable
+pub type ImmutableRefs = BTreeMap<String, Vec<ImmutableRef>>;
+
+/**
+ * Reads the immutable references from the forge artifact at `out/<File>.sol/<ContractName>.json`.
+ * Without a contract path the first artifact with the name of the contract is used.
+ */
+pub fn read_immutable_references(project_path: &Path, contract_name: &str, contract_path: Option<&str>) -> Result<ImmutableRefs> {
+    let out = project_path.join("out");
+    let file_name = format!("{}.json", contract_name);
+
+    let artifact_path = match contract_path.and_then(|path| Path::new(path).file_name()) {
+        Some(source_file) => out.join(source_file).join(&file_name),
+        None => find_artifact(&out, &file_name)
+            .ok_or_else(|| eyre::eyre!("Could not find the artifact {} in {}", file_name, out.display()))?,
+    };
+
+    let artifact = std::fs::read_to_string(&artifact_path)
+        .map_err(|err| eyre::eyre!("Could not read forge artifact {}: {}", artifact_path.display(), err))?;
+    let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+
+    match artifact["deployedBytecode"].get("immutableReferences") {
+        Some(references) => Ok(serde_json::from_value(references.clone())?),
+        None => Ok(ImmutableRefs::new()),
+    }
+}
+
+/**
+ * Searches the artifact directories (one per source file) for the artifact
+ */
+fn find_artifact(out: &Path, file_name: &str) -> Option<PathBuf> {
+    let mut source_dirs: Vec<PathBuf> = std::fs::read_dir(out).ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    source_dirs.sort();
+
+    source_dirs.into_iter()
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+/**
+ * Overwrites every immutable in the bytecode with zeros, like solc leaves them before deployment.
+ * References that are out of range (e.g. a different bytecode) are ignored.
+ */
+pub fn zero_immutables(bytecode: &mut [u8], refs: &ImmutableRefs) {
+    for reference in refs.values().flatten() {
+        if let Some(range) = bytecode.get_mut(reference.start..reference.start + reference.length) {
+            range.fill(0);
+        }
+    }
+}
+
+/**
+ * Reads the value of every immutable from the deployed bytecode, keyed by the AST id of the variable.
+ * An immutable that is used more than once has the same value at every reference, so the first one is read.
+ */
+pub fn extract_immutable_values(bytecode: &[u8], refs: &ImmutableRefs) -> HashMap<String, Vec<u8>> {
+    refs.iter()
+        .filter_map(|(id, references)| {
+            let reference = references.first()?;
+            let value = bytecode.get(reference.start..reference.start + reference.length)?;
+            Some((id.clone(), value.to_vec()))
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+use crate::erc4337::{call_outcome, CallOutcome};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Log, H256},
+    utils::id,
+};
+use eyre::Result;
+
+/// `Initialized(uint8)` of OpenZeppelin `Initializable` 4.x and `Initialized(uint64)` of 5.x
+const INITIALIZED_TOPICS: [[u8; 32]; 2] = [
+    [
+        0x7f, 0x26, 0xb8, 0x3f, 0xf9, 0x6e, 0x1f, 0x2b, 0x6a, 0x68, 0x2f, 0x13, 0x38, 0x52, 0xf6, 0x79,
+        0x8a, 0x09, 0xc4, 0x65, 0xda, 0x95, 0x92, 0x14, 0x60, 0xce, 0xfb, 0x38, 0x47, 0x40, 0x24, 0x98,
+    ],
+    [
+        0xc7, 0xf5, 0x05, 0xb2, 0xf3, 0x71, 0xae, 0x21, 0x75, 0xee, 0x49, 0x13, 0xf4, 0x49, 0x9e, 0x1f,
+        0x26, 0x33, 0xa7, 0xb5, 0x93, 0x63, 0x21, 0xee, 0xd1, 0xcd, 0xae, 0xb6, 0x11, 0x51, 0x81, 0xd2,
+    ],
+];
+
+/**
+ * Whether an upgradeable contract was initialized exactly once and can't be initialized again
+ */
+#[derive(Debug, Clone)]
+pub struct InitializationReport {
+    /// Number of `Initialized` events the contract emitted in the deployment transaction
+    pub initialized_events: usize,
+    /// The outcome of calling `initialize()` at the latest block
+    pub initialize_call: CallOutcome,
+}
+
+impl InitializationReport {
+    pub fn initialized_once(&self) -> bool {
+        self.initialized_events == 1
+    }
+
+    /// A successful call means anyone can (re-)initialize the contract and take it over
+    pub fn can_be_initialized(&self) -> bool {
+        self.initialize_call == CallOutcome::Succeeded
+    }
+}
+
+/**
+ * Counts the `Initialized` events of the contract in the logs of the deployment transaction and calls `initialize()`,
+ * which has to revert once the contract is initialized. Only the parameterless `initialize()` can be called this way.
+ */
+pub async fn check_initialization_state<M: Middleware>(provider: &M, address: Address, logs: &[Log]) -> Result<InitializationReport> {
+    let initialized_events = logs
+        .iter()
+        .filter(|log| log.address == address)
+        .filter(|log| log.topics.first().is_some_and(|topic| INITIALIZED_TOPICS.iter().any(|initialized| H256::from(*initialized) == *topic)))
+        .count();
+
+    Ok(InitializationReport {
+        initialized_events,
+        initialize_call: call_outcome(provider, address, id("initialize()").to_vec()).await?,
+    })
+}
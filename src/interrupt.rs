@@ -0,0 +1,172 @@
+use crate::error::VerifyError;
+use eyre::Result;
+use std::{
+    io::Read,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Exit code used when the user pressed CTRL-C
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Exit code used when `--timeout` expired, the same as `timeout(1)`
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static CLEANUP_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/**
+ * Installs the CTRL-C handler, the handler only sets a flag that is checked at every subprocess boundary
+ */
+pub fn install_handler() -> Result<()> {
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst))?;
+    Ok(())
+}
+
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/**
+ * Registers a temporary directory that should be removed when the user presses CTRL-C
+ */
+pub fn register_cleanup(path: PathBuf) {
+    CLEANUP_PATHS.lock().unwrap().push(path);
+}
+
+/**
+ * Removes all registered temporary directories
+ */
+pub fn cleanup() {
+    for path in CLEANUP_PATHS.lock().unwrap().drain(..) {
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
+
+/**
+ * Stops the verification like CTRL-C would: running subprocesses are killed and the temporary directories removed
+ */
+pub fn abort() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+
+    // Give `run_command` the chance to kill its child before the directory it works in is removed
+    thread::sleep(Duration::from_millis(100));
+    cleanup();
+}
+
+/**
+ * Runs the command to completion like `Command::output`, but kills it as soon as the user presses CTRL-C
+ */
+pub fn run_command(command: &mut Command) -> Result<Output> {
+    if is_interrupted() {
+        return Err(eyre::eyre!("Interrupted"));
+    }
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Read the output on separate threads so a full pipe can not block the child
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    let stdout_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stdout.read_to_end(&mut buffer);
+        buffer
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stderr.read_to_end(&mut buffer);
+        buffer
+    });
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if is_interrupted() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(eyre::eyre!("Interrupted"));
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/**
+ * Runs the command like `run_command`, but fails with its stderr when it exits with a non-zero code
+ */
+pub fn run_checked(command: &mut Command) -> Result<Output> {
+    let output = run_command(command)?;
+    check_output(command, output)
+}
+
+/**
+ * The async version of `run_command` for `tokio::process`, the child is killed when the user presses CTRL-C
+ */
+pub async fn run_command_async(command: &mut tokio::process::Command) -> Result<Output> {
+    if is_interrupted() {
+        return Err(eyre::eyre!("Interrupted"));
+    }
+
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // Dropping the output future drops the child, which kills it
+    let output = child.wait_with_output();
+    tokio::pin!(output);
+    loop {
+        tokio::select! {
+            result = &mut output => return Ok(result?),
+            _ = tokio::time::sleep(Duration::from_millis(50)) => {
+                if is_interrupted() {
+                    return Err(eyre::eyre!("Interrupted"));
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Runs the command like `run_command_async`, but fails with its stderr when it exits with a non-zero code
+ */
+pub async fn run_checked_async(command: &mut tokio::process::Command) -> Result<Output> {
+    let output = run_command_async(command).await?;
+    check_output(command.as_std(), output)
+}
+
+/**
+ * Turns the output of a command that exited with a non-zero code into a `VerifyError::SubprocessError`
+ */
+pub fn check_output(command: &Command, output: Output) -> Result<Output> {
+    if !output.status.success() {
+        return Err(VerifyError::SubprocessError {
+            command: VerifyError::format_command(command),
+            exit_code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }
+        .into());
+    }
+
+    Ok(output)
+}
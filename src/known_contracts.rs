@@ -0,0 +1,44 @@
+use ethers::utils::{hex, keccak256};
+use eyre::Result;
+use std::{collections::HashMap, path::Path};
+
+/**
+ * A database of known bytecodes, mapping the keccak256 hash of the metadata-stripped bytecode to a description
+ * of the contract (e.g. "OpenZeppelin ERC20 v4.9.0"). Stored as a JSON object or a TOML table.
+ */
+pub struct KnownContractsDb {
+    contracts: HashMap<String, String>,
+}
+
+impl KnownContractsDb {
+    /**
+     * Loads the database, files ending in `.toml` are parsed as TOML and everything else as JSON
+     */
+    pub fn load(path: &Path) -> Result<KnownContractsDb> {
+        let content = std::fs::read_to_string(path)?;
+
+        let entries: HashMap<String, String> = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            _ => serde_json::from_str(&content)?,
+        };
+
+        // Normalize the hashes so lookups don't depend on how they were written
+        let contracts = entries
+            .into_iter()
+            .map(|(hash, name)| (normalize_hash(&hash), name))
+            .collect();
+
+        Ok(KnownContractsDb { contracts })
+    }
+
+    /**
+     * Looks up the bytecode by its hash
+     */
+    pub fn lookup(&self, bytecode: &[u8]) -> Option<&String> {
+        self.contracts.get(&hex::encode(keccak256(bytecode)))
+    }
+}
+
+fn normalize_hash(hash: &str) -> String {
+    hash.trim().trim_start_matches("0x").to_lowercase()
+}
@@ -1,14 +1,102 @@
 use ethers::{
-    providers::{Http, Middleware, Provider},
-    types::{ActionType, Address, Create, CreateResult, Trace, H256},
+    abi::{Abi, Event as AbiEvent},
+    providers::{call_raw::spoof, Http, JsonRpcClient, Middleware, Provider, RawCall},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, Log, TransactionRequest, H256, U256},
+    utils::{hex, keccak256},
 };
+use chrono::{TimeZone, Utc};
 use eyre::Result;
 use which::which;
-use std::{env, path::{PathBuf, Path}, process::Command, sync::Arc};
+use std::{env, path::{PathBuf, Path}, process::{Command, Output}, sync::Arc};
+use tokio::process::Command as AsyncCommand;
 use spinoff::{Spinner, spinners, Color};
 use clap::Parser;
 use std::str;
+use std::io::IsTerminal;
 use interactive_clap::{ResultFromCli, ToCliArgs};
+use access_control::detect_access_control;
+use analyzer::BytecodeAnalyzer;
+use code_size::CodeSize;
+use contract_ref::validate_contract_name;
+use contract_type::{ContractStandard, ContractTypeChecker};
+use dependencies::check_dependency_versions;
+use diamond::{DiamondFacetVerifier, FacetsManifest};
+use deployment::{compute_create2_address, compute_create_address, ContractDeployment, DeploymentFinder};
+use erc4337::{check_erc4337_compliance, CallOutcome};
+use events::match_event_signatures;
+use explorer::explorer_url;
+use initialization::check_initialization_state;
+use interrupt::{check_output, run_checked, run_checked_async, run_command, run_command_async, INTERRUPTED_EXIT_CODE, TIMEOUT_EXIT_CODE};
+use known_contracts::KnownContractsDb;
+use metadata::{detect_compiler_version, ContractMetadata};
+use signatures::{canonical_signature, SignatureExport};
+use solc_versions::{install_solc_version, list_solc_versions};
+use sources::{verify_source_hashes, DEFAULT_IPFS_GATEWAY, DEFAULT_SWARM_GATEWAY};
+use fingerprint::BytecodeFingerprint;
+use git_cache::GitWorktreeCache;
+use git_url::sanitize_git_url;
+use html::render_html;
+use immutables::{extract_immutable_values, read_immutable_references, zero_immutables};
+use proof::{load_signing_key, sha256_hex, sign_proof, VerificationProof};
+use proxy::{detect_proxy_patterns, read_proxy_admin, ProxyPattern};
+use rate_limit::{RateLimitedClient, RateLimiter};
+use report::{differing_bytes, match_bytecodes, FullVerifyReport, MatchKind, MatchOptions, VerifyResult, format_csv, format_output, save_report, similarity};
+use sbom::{generate_sbom, save_sbom, ForgeArtifact};
+use diff::{diff_opcodes, format_opcode_diff, BytecodeDiffStats};
+use storage_layout::{StorageCollisionChecker, StorageLayout, StorageLayoutDiff};
+use timing::{commit_timestamp, verify_commit_predates_deployment, CommitTimingStatus};
+use trace_provider::{trace_transaction, trace_transaction_cached, TraceProvider};
+use traces::{format_trace_table, format_trace_tree, TraceRow};
+use upgrade_safety::{DeployedContract, UpgradeSafetyReport};
+use manifest::{load_manifest, save_manifest};
+use natspec::{NatSpec, NatSpecReport};
+use opcode_policy::{OpcodeList, OpcodePolicy, Policy};
+use pausable::check_pauseability;
+use opcodes::{constructor_code, disassemble, find_calls_in_constructor, has_receive_or_fallback_with_revert, histogram_distance, opcode_histogram, opcode_name};
+
+mod access_control;
+mod analyzer;
+mod code_size;
+mod contract_ref;
+mod contract_type;
+mod dependencies;
+mod diamond;
+mod deployment;
+mod diff;
+mod erc4337;
+mod error;
+mod events;
+mod explorer;
+mod fingerprint;
+mod git_cache;
+mod git_url;
+mod html;
+mod immutables;
+mod initialization;
+mod interrupt;
+mod known_contracts;
+mod manifest;
+mod metadata;
+#[cfg(test)]
+mod mock_provider;
+mod natspec;
+mod opcode_policy;
+mod opcodes;
+mod pausable;
+mod profiles;
+mod proof;
+mod proxy;
+mod rate_limit;
+mod report;
+mod sbom;
+mod signatures;
+mod solc_versions;
+mod sources;
+mod timing;
+mod storage_layout;
+mod trace_provider;
+mod traces;
+mod upgrade_safety;
 
 #[derive(Parser, Debug, interactive_clap::InteractiveClap)]
 #[command(author, version, about, long_about = None)]
@@ -17,199 +105,3114 @@ struct Args {
     #[interactive_clap(long)]
     transaction: String,
 
-    /// Address of the contract that should be checked
-    #[interactive_clap(long)]
-    contract_address: String,
+    /// Address of the contract that should be checked
+    #[interactive_clap(long)]
+    contract_address: String,
+
+    /// Optional: comma separated addresses of contracts to verify against the same source and transaction, instead of `--contract-address`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    contract_addresses: Option<String>,
+
+    /// Verify every contract created in the transaction, instead of `--contract-address`
+    #[interactive_clap(long)]
+    all_contracts: bool,
+
+    /// Fail with the list of missing arguments instead of prompting for them
+    #[interactive_clap(long)]
+    no_interactive: bool,
+
+    /// Optional: name of the contract to look up the address of in the deployment manifests, instead of `--contract-address`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    contract_address_alias: Option<String>,
+
+    /// Optional: directory with the deployment manifests, as `<chainId>/<ContractName>.json` (defaults to `deployments`)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    deployments_dir: Option<String>,
+
+    /// Git url of the repository to check against
+    #[interactive_clap(long)]
+    git: String,
+
+    /// Validate and normalize the git urls before they are passed to `git`
+    #[interactive_clap(long)]
+    sanitize_git_url: bool,
+
+    /// Optional: commit hash, tag or branch of the git repo
+    #[interactive_clap(long)]
+    commit: String,
+
+    /// Optional: how to interpret `--commit`: tag, branch, commit or auto (default)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    ref_type: Option<RefType>,
+
+    /// Optional: tag of the git repo to check against, instead of `--commit`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    git_tag: Option<String>,
+
+    /// Optional: branch of the git repo to check against, instead of `--commit`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    git_branch: Option<String>,
+
+    /// Name of the contract (in the git repository) to check against
+    #[interactive_clap(long)]
+    contract_name: String,
+
+    /// HTTP RPC url (has to support `trace` calls)
+    #[interactive_clap(long)]
+    rpc: String,
+
+    /// Optional: path of the contract source file inside the repository, `.vy` files are compiled with Vyper
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    contract_path: Option<String>,
+
+    /// Optional: compiler backend to use: forge, vyper, ape or hardhat (detected from the project and `--contract-path` by default)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    backend: Option<Compiler>,
+
+    /// Optional: path of the `vyper` binary
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    vyper_path: Option<String>,
+
+    /// Optional: value of `HARDHAT_NETWORK` when compiling with hardhat (defaults to `hardhat`)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    hardhat_network_name: Option<String>,
+
+    /// Optional: path of the hardhat config file relative to the project root (detected by default)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    hardhat_config_path: Option<String>,
+
+    /// Run `forge inspect` with `--json` and parse the bytecode from the JSON output
+    #[interactive_clap(long)]
+    foundry_json_output: bool,
+
+    /// Optional: foundry.toml settings to override, as a comma separated list of `key=value` pairs
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    foundry_config_override: Option<String>,
+
+    /// Simulate the deployment of `--calldata` with `eth_call` instead of tracing `--transaction`
+    #[interactive_clap(long)]
+    simulate: bool,
+
+    /// Compare the deployed (runtime) bytecode instead of the init code, ignoring the values of immutables
+    #[interactive_clap(long)]
+    deployed: bool,
+
+    /// Optional: compare against the code at `--contract-address` at this block instead of the deployment, implies `--deployed`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    historical_bytecode: Option<u64>,
+
+    /// Only list the traces of the transaction, without verifying anything
+    #[interactive_clap(long)]
+    list_traces: bool,
+
+    /// Only list the traces of the transaction as JSON, without verifying anything
+    #[interactive_clap(long)]
+    list_traces_json: bool,
+
+    /// Only print the traces of the transaction as a call tree, without verifying anything
+    #[interactive_clap(long)]
+    print_trace_tree: bool,
+
+    /// Only print the solc version from the metadata of `--contract-address` (or `--contract-addresses`, or `--expected-bytecode`), without compiling
+    #[interactive_clap(long)]
+    detect_compiler: bool,
+
+    /// Only print the solc versions that can be installed, newest first
+    #[interactive_clap(long)]
+    list_solc_versions: bool,
+
+    /// Optional: install this solc version with `svm install` before compiling
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    install_solc_version: Option<String>,
+
+    /// Optional: which of the matching `CREATE` traces to use when the contract was created more than once in the transaction (starting at 0)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    trace_index: Option<u64>,
+
+    /// Optional: only consider `CREATE` traces of this deployer, when the address was deployed more than once
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    tx_from_address: Option<String>,
+
+    /// Optional: deepest level of the trace tree to search for the `CREATE`, 0 is the top-level call and 1 includes its direct subcalls
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    max_trace_depth: Option<u64>,
+
+    /// Optional: how the RPC traces transactions, `parity` (`trace_transaction`), `debug` (`debug_traceTransaction`) or `auto` (default)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    trace_provider_type: Option<TraceProvider>,
+
+    /// Remember which provider type worked in `auto` mode, keyed by a hash of the RPC url
+    #[interactive_clap(long)]
+    cache_trace_provider: bool,
+
+    /// Optional: timeout in milliseconds to retry tracing with `debug_traceTransaction` when the RPC gave up on the trace
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    trace_timeout: Option<u64>,
+
+    /// Fail if the deployed code looks like a proxy (EIP-1167, transparent, UUPS or diamond)
+    #[interactive_clap(long)]
+    check_no_proxy: bool,
+
+    /// Fail if the deployed code has no `CALLVALUE` guard, so it may accept ETH
+    #[interactive_clap(long)]
+    require_reject_eth: bool,
+
+    /// Report whether the contract can be paused (`pause()` / `unpause()`) and whether it is paused
+    #[interactive_clap(long)]
+    check_pause_mechanism: bool,
+
+    /// Fail unless the contract has `pause()` and `unpause()`, implies `--check-pause-mechanism`
+    #[interactive_clap(long)]
+    require_pauseable: bool,
+
+    /// Fail if `paused()` returns true, implies `--check-pause-mechanism`
+    #[interactive_clap(long)]
+    require_not_paused: bool,
+
+    /// Check that the contract implements the ERC-4337 validation functions and rejects an invalid `UserOperation`
+    #[interactive_clap(long)]
+    check_erc4337_entrypoint: bool,
+
+    /// Check that `--contract-address` is the `CREATE` address of the sender and nonce of `--transaction`
+    #[interactive_clap(long)]
+    verify_nonce: bool,
+
+    /// Check that an upgradeable contract emitted `Initialized` exactly once during deployment and `initialize()` reverts now
+    #[interactive_clap(long)]
+    check_initialize_not_called: bool,
+
+    /// Optional: RPC URL of a second chain, the deployed code at `--contract-address` on it is compared to the compiled deployed bytecode
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    cross_verify: Option<String>,
+
+    /// Optional: maximum number of RPC requests per second, to stay within the limits of the provider
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    rpc_rate_limit: Option<u64>,
+
+    /// Optional: abort the whole verification after this many seconds
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    timeout: Option<u64>,
+
+    /// Optional: chain ID of the network, overrides the chain ID reported by the RPC
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    chain_id: Option<u64>,
+
+    /// Optional: the on-chain init code as hex, to compare against offline instead of tracing `--transaction` through `--rpc`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    expected_bytecode: Option<String>,
+
+    /// Optional: hex encoded init code (including constructor arguments) to simulate in `--simulate` mode
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    calldata: Option<String>,
+
+    /// Optional: write a JSON report of the verification to this path
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    output_report: Option<String>,
+
+    /// Keep a clone of the repository in the cache directory and check out commits as git worktrees, instead of cloning on every run
+    #[interactive_clap(long)]
+    git_cache: bool,
+
+    /// Print a signed proof of the verification as JSON, requires `--signing-key`
+    #[interactive_clap(long)]
+    prove: bool,
+
+    /// Optional: file with the hex encoded ed25519 private key to sign the `--prove` proof with
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    signing_key: Option<String>,
+
+    /// Compare the storage layout with the previous version at `--previous-git` and `--previous-commit`, for upgrade safety
+    #[interactive_clap(long)]
+    compare_storage_layout: bool,
+
+    /// Optional: git url of the previous version for `--compare-storage-layout` (defaults to `--git`)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    previous_git: Option<String>,
+
+    /// Optional: commit hash, tag or branch of the previous version for `--compare-storage-layout`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    previous_commit: Option<String>,
+
+    /// Compare the functions, events and storage layout with the implementation at `--old-contract-address`
+    #[interactive_clap(long)]
+    check_upgrade_safety: bool,
+
+    /// Optional: address of the implementation that is being upgraded, for `--check-upgrade-safety`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    old_contract_address: Option<String>,
+
+    /// Optional: fail unless `forge --version` contains this version, e.g. `0.2.0 (a1b2c3d`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    forge_version: Option<String>,
+
+    /// Optional: install this forge version with `foundryup --version` before compiling
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    install_forge_version: Option<String>,
+
+    /// Print the solc version from `foundry.toml` before compiling
+    #[interactive_clap(long)]
+    print_solc_version: bool,
+
+    /// Optional: run `forge` in a container of this Docker image, with the cloned project mounted into it
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    docker_build: Option<String>,
+
+    /// Optional: block number to fork `--rpc` at while running `--use-forge-script`, passed to `forge script` as `--fork-block-number`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    fork_block: Option<u64>,
+
+    /// Optional: deployment script to take the init code from, run with `forge script <path> --json` instead of `forge inspect`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    use_forge_script: Option<String>,
+
+    /// Pass the remappings detected by `forge remappings` to `forge inspect`
+    #[interactive_clap(long)]
+    forge_remappings_auto: bool,
+
+    /// Optional: write the raw output of `forge inspect` (exit code, stdout and stderr) as JSON to this path
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    save_forge_output: Option<String>,
+
+    /// Optional: write the raw traces of the transaction as JSON to this path
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    save_trace_output: Option<String>,
+
+    /// Optional: after a successful verification, write its arguments and result as a TOML manifest to this path
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    output_manifest: Option<String>,
+
+    /// Optional: verify every contract of a TOML manifest written by `--output-manifest`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    manifest: Option<String>,
+
+    /// Optional: write the hex of the init bytecode from the trace (including metadata and constructor arguments) to this path
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    output_init_bytecode: Option<String>,
+
+    /// Optional: write the hex of the compiled init bytecode (including metadata) to this path
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    output_compiled_init_bytecode: Option<String>,
+
+    /// Optional: write the signatures and selectors of all functions and events in the ABI to this JSON file
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    export_function_sigs: Option<String>,
+
+    /// Optional: path to write a CycloneDX SBOM of the compiled contract to (compiler, sources and `lib/` dependencies)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    generate_sbom: Option<String>,
+
+    /// Refuse to verify without a pinned `--commit`, recommended for production verification scripts
+    #[interactive_clap(long)]
+    require_commit: bool,
+
+    /// Verify that the commit exists in the remote repository before cloning
+    #[interactive_clap(long)]
+    anchor_commit: bool,
+
+    /// Optional: JSON or TOML file mapping keccak256 hashes of metadata-stripped bytecode to known contract names
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    known_contracts_db: Option<String>,
+
+    /// Compare the events emitted during deployment with the events in the compiled ABI
+    #[interactive_clap(long)]
+    compare_events: bool,
+
+    /// Also accept the on-chain bytecode if the compiled bytecode is a prefix of it
+    #[interactive_clap(long)]
+    accept_partial_match: bool,
+
+    /// Compare the source files with the hashes in the on-chain metadata JSON (fetched from IPFS)
+    #[interactive_clap(long)]
+    check_source_hashes: bool,
+
+    /// Optional: IPFS gateway used to fetch the metadata JSON (defaults to https://ipfs.io/ipfs/)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    ipfs_gateway: Option<String>,
+
+    /// Fetch the metadata JSON of contracts with a Swarm hash (solc < 0.6) from a Swarm gateway
+    #[interactive_clap(long)]
+    fetch_swarm: bool,
+
+    /// Optional: Swarm gateway used with `--fetch-swarm` (defaults to https://swarm-gateways.net/bzz-raw:/)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    swarm_gateway: Option<String>,
+
+    /// Optional: also accept the deployment if the similarity of the bytecodes is at least this threshold (0.0 - 1.0)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    fuzzy_match: Option<String>,
+
+    /// Optional: also accept the deployment if at most this many bytes differ (0 by default)
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    diff_threshold: Option<u64>,
+
+    /// Optional: check that the deployed contract implements all required functions of a standard: erc20, erc721, erc1155 or erc4626
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    check_contract_type: Option<ContractStandard>,
+
+    /// Optional: expected admin of the transparent proxy, compared with the EIP-1967 admin slot
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    check_proxy_admin: Option<String>,
+
+    /// Optional: name of the proxy contract in the same project, whose storage layout is checked for slots shared with the verified implementation
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    check_storage_collision: Option<String>,
+
+    /// Fail instead of warning if the init code exceeds the EIP-3860 limit or the deployed code exceeds the EIP-170 limit
+    #[interactive_clap(long)]
+    check_constructor_bytecode_size: bool,
+
+    /// Open the contract on the block explorer of the chain in the default browser after verifying
+    #[interactive_clap(long)]
+    open_explorer: bool,
+
+    /// Print the url of the contract on the block explorer of the chain after verifying
+    #[interactive_clap(long)]
+    print_explorer_url: bool,
+
+    /// Optional: 32 byte hex salt the contract was deployed with, checks that `--contract-address` is the CREATE2 address of `--deployer`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    verify_create2_salt: Option<String>,
+
+    /// Optional: factory that deployed the contract with CREATE2, for `--verify-create2-salt`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    deployer: Option<String>,
+
+    /// Optional: keccak256 hash of the init code for `--verify-create2-salt`, computed from the compiled bytecode if not set
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    expected_init_code_hash: Option<String>,
+
+    /// Optional: comma separated opcodes (names like `CALL` or values like `0xf1`), the deployed code may only use these
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    opcode_allow_list: Option<OpcodeList>,
+
+    /// Optional: comma separated opcodes (names like `SELFDESTRUCT` or values like `0xff`) the deployed code must not use
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    opcode_deny_list: Option<OpcodeList>,
+
+    /// Read the facets of the EIP-2535 diamond with `facets()` and verify each of them against `--facets-manifest`
+    #[interactive_clap(long)]
+    check_diamond_facets: bool,
+
+    /// Optional: TOML file with a `[facets.<address>]` table (`git`, `commit`, `contract_name`, `transaction`) per facet
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    facets_manifest: Option<String>,
+
+    /// Optional: `StrictHostKeyChecking` for git over ssh: yes, no or accept-new
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    git_ssh_strict_host_checking: Option<StrictHostKeyChecking>,
+
+    /// Report ownership and role based access control patterns (Ownable, Ownable2Step, AccessControl)
+    #[interactive_clap(long)]
+    check_access_control: bool,
+
+    /// Check that every function in the ABI has NatSpec documentation
+    #[interactive_clap(long)]
+    compare_natspec: bool,
+
+    /// Optional: JSON file with the expected `devdoc` and `userdoc`, compared with the compiled NatSpec by `--compare-natspec`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    expected_natspec: Option<String>,
+
+    /// Check that the versions of the dependencies (e.g. OpenZeppelin) match the tags of their pinned commits
+    #[interactive_clap(long)]
+    check_dependencies: bool,
+
+    /// Fail if the constructor of the traced init code contains a `DELEGATECALL`
+    #[interactive_clap(long)]
+    strict_constructor: bool,
+
+    /// Warn about every external call (`CALL`, `STATICCALL`, `DELEGATECALL`, `CALLCODE`) in the constructor
+    #[interactive_clap(long)]
+    check_no_external_calls_in_constructor: bool,
+
+    /// Print byte level statistics about the differences between the bytecodes
+    #[interactive_clap(long)]
+    diff_stats: bool,
+
+    /// Report whether only the metadata differs or the code itself, with the CBOR metadata of both bytecodes
+    #[interactive_clap(long)]
+    compare_metadata_only: bool,
+
+    /// Print an opcode level diff of the bytecodes when they don't match
+    #[interactive_clap(long)]
+    output_opcodes_diff: bool,
+
+    /// Optional: format of the result: text (default), json, html or csv. With json, html and csv all other messages are written to stderr
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    output: Option<OutputFormat>,
+
+    /// Optional: print the result with this template instead, e.g. `{contract} {match} {similarity}`
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    format_output: Option<String>,
+
+    /// Only print warnings and the result
+    #[interactive_clap(long)]
+    quiet: bool,
+
+    /// Shorthand for `--no-spinner --output json --quiet`, also exits with a non-zero code when the bytecode does not match
+    #[interactive_clap(long)]
+    ci: bool,
+
+    /// Exit with an error when any warning was reported, even if the bytecode matched
+    #[interactive_clap(long)]
+    fail_on_warning: bool,
+
+    /// Print additional details about the comparison
+    #[interactive_clap(long)]
+    verbose: bool,
+
+    /// Optional: load arguments from a named profile in `~/.config/bytematch/profiles.toml`, explicit arguments take precedence
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    profile: Option<String>,
+
+    /// Optional: save the arguments of this invocation as a named profile
+    #[interactive_clap(long)]
+    #[interactive_clap(skip_interactive_input)]
+    save_profile: Option<String>,
+
+    /// Disable the progress spinner (automatically disabled when stdout is not a terminal)
+    #[interactive_clap(long)]
+    no_spinner: bool,
+}
+
+/**
+ * The kind of git reference passed to `--commit`
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RefType {
+    Tag,
+    Branch,
+    Commit,
+    Auto,
+}
+
+impl str::FromStr for RefType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tag" => Ok(RefType::Tag),
+            "branch" => Ok(RefType::Branch),
+            "commit" => Ok(RefType::Commit),
+            "auto" => Ok(RefType::Auto),
+            _ => Err(format!("Unknown ref type '{}', expected one of: tag, branch, commit, auto", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for RefType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefType::Tag => write!(f, "tag"),
+            RefType::Branch => write!(f, "branch"),
+            RefType::Commit => write!(f, "commit"),
+            RefType::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl interactive_clap::ToCli for RefType {
+    type CliVariant = RefType;
+}
+
+impl RefType {
+    /**
+     * Resolves `Auto` by looking at the reference: a hex string of 7 to 40 characters is a (possibly abbreviated) commit,
+     * `v<major>.<minor>...` is a tag and anything else is a branch
+     */
+    fn resolve(self, reference: &str) -> RefType {
+        if self != RefType::Auto {
+            return self;
+        }
+
+        if (7..=40).contains(&reference.len()) && reference.chars().all(|c| c.is_ascii_hexdigit()) {
+            return RefType::Commit;
+        }
+
+        let mut version = reference.strip_prefix('v').unwrap_or("").split('.');
+        let is_number = |part: Option<&str>| part.is_some_and(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+        if is_number(version.next()) && is_number(version.next()) {
+            return RefType::Tag;
+        }
+
+        RefType::Branch
+    }
+}
+
+/**
+ * The compiler used to build the contract
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compiler {
+    Forge,
+    Vyper,
+    Ape,
+    Hardhat,
+}
+
+impl str::FromStr for Compiler {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "forge" => Ok(Compiler::Forge),
+            "vyper" => Ok(Compiler::Vyper),
+            "ape" => Ok(Compiler::Ape),
+            "hardhat" => Ok(Compiler::Hardhat),
+            _ => Err(format!("Unknown backend '{}', expected one of: forge, vyper, ape, hardhat", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Compiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compiler::Forge => write!(f, "forge"),
+            Compiler::Vyper => write!(f, "vyper"),
+            Compiler::Ape => write!(f, "ape"),
+            Compiler::Hardhat => write!(f, "hardhat"),
+        }
+    }
+}
+
+impl interactive_clap::ToCli for Compiler {
+    type CliVariant = Compiler;
+}
+
+impl Compiler {
+    /**
+     * Picks the compiler based on the project configuration and the extension of the contract source file
+     */
+    fn detect(project_path: &Path, contract_path: Option<&str>) -> Compiler {
+        if project_path.join(APE_CONFIG).exists() {
+            return Compiler::Ape;
+        }
+
+        // Projects that use both usually build with forge
+        let is_hardhat = HARDHAT_CONFIGS.iter().any(|config| project_path.join(config).exists());
+        if is_hardhat && !project_path.join("foundry.toml").exists() {
+            return Compiler::Hardhat;
+        }
+
+        match contract_path {
+            Some(path) if path.ends_with(".vy") => Compiler::Vyper,
+            _ => Compiler::Forge,
+        }
+    }
+}
+
+/**
+ * The git reference to checkout
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum GitRef {
+    Tag(String),
+    Branch(String),
+    Commit(String),
+}
+
+impl GitRef {
+    /**
+     * Interprets the value of `--commit` according to `--ref-type`
+     */
+    fn new(reference: &str, ref_type: RefType) -> GitRef {
+        match ref_type.resolve(reference) {
+            RefType::Tag => GitRef::Tag(reference.to_string()),
+            RefType::Branch => GitRef::Branch(reference.to_string()),
+            _ => GitRef::Commit(reference.to_string()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            GitRef::Tag(name) | GitRef::Branch(name) | GitRef::Commit(name) => name,
+        }
+    }
+}
+
+/**
+ * Reports progress to the user, either using a spinner or, when the spinner is disabled, as plain lines on stderr
+ */
+struct Progress {
+    spinner: Option<Spinner>,
+    quiet: bool,
+}
+
+impl Progress {
+    fn new(use_spinner: bool, quiet: bool, message: &str) -> Progress {
+        if use_spinner {
+            return Progress {
+                spinner: Some(Spinner::new(spinners::Dots, message.to_string(), Color::Blue)),
+                quiet,
+            };
+        }
+
+        if !quiet {
+            eprintln!("{}", message);
+        }
+        Progress { spinner: None, quiet }
+    }
+
+    fn update(&mut self, message: &str) {
+        match self.spinner.as_mut() {
+            Some(spinner) => spinner.update(spinners::Dots, message.to_string(), Color::Blue),
+            None if !self.quiet => eprintln!("{}", message),
+            None => {}
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut spinner) = self.spinner.take() {
+            spinner.stop();
+        }
+    }
+}
+
+impl Drop for Progress {
+    // Make sure the spinner does not keep running when we return early
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/**
+ * Prints human readable messages, they go to stderr when stdout is reserved for machine readable output
+ */
+struct Logger {
+    to_stderr: bool,
+    quiet: bool,
+}
+
+impl Logger {
+    fn info(&self, message: &str) {
+        if self.quiet {
+            return;
+        }
+
+        if self.to_stderr {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /**
+     * The result is printed even in quiet mode, unless it is printed in a machine readable format on stdout
+     */
+    fn result(&self, message: &str) {
+        if !self.to_stderr {
+            println!("{}", message);
+        } else if !self.quiet {
+            eprintln!("{}", message);
+        }
+    }
+
+    /**
+     * Warnings are printed even in quiet mode
+     */
+    fn warn(&self, message: &str) {
+        if self.to_stderr {
+            eprintln!("Warning: {}", message);
+        } else {
+            println!("Warning: {}", message);
+        }
+    }
+}
+
+/**
+ * The format of the final result
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Html,
+    Csv,
+}
+
+impl str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "html" => Ok(OutputFormat::Html),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("Unknown output format '{}', expected one of: text, json, html, csv", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Html => write!(f, "html"),
+            OutputFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+impl interactive_clap::ToCli for OutputFormat {
+    type CliVariant = OutputFormat;
+}
+
+/**
+ * The `StrictHostKeyChecking` option of ssh for git urls like `git@github.com:org/repo.git`
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StrictHostKeyChecking {
+    Yes,
+    No,
+    AcceptNew,
+}
+
+impl str::FromStr for StrictHostKeyChecking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yes" => Ok(StrictHostKeyChecking::Yes),
+            "no" => Ok(StrictHostKeyChecking::No),
+            "accept-new" => Ok(StrictHostKeyChecking::AcceptNew),
+            _ => Err(format!("Unknown host key checking '{}', expected one of: yes, no, accept-new", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for StrictHostKeyChecking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrictHostKeyChecking::Yes => write!(f, "yes"),
+            StrictHostKeyChecking::No => write!(f, "no"),
+            StrictHostKeyChecking::AcceptNew => write!(f, "accept-new"),
+        }
+    }
+}
+
+impl interactive_clap::ToCli for StrictHostKeyChecking {
+    type CliVariant = StrictHostKeyChecking;
+}
+
+/**
+ * Resolved paths of the external binaries the tool depends on
+ */
+struct Prerequisites {
+    git: PathBuf,
+    forge: PathBuf,
+    yarn: Option<PathBuf>,
+    npm: Option<PathBuf>,
+    docker: Option<PathBuf>,
+    /// Image that `forge` runs in with `--docker-build`
+    docker_image: Option<String>,
+    /// `GIT_SSH_COMMAND` of the commands that clone or fetch, with `--git-ssh-strict-host-checking`
+    git_ssh_command: Option<String>,
+}
+
+impl Prerequisites {
+    /**
+     * The environment of a command that may reach a git remote, empty unless `--git-ssh-strict-host-checking` is set
+     */
+    fn ssh_env(&self) -> Option<(&str, &str)> {
+        self.git_ssh_command.as_deref().map(|command| ("GIT_SSH_COMMAND", command))
+    }
+}
+
+/**
+ * Checks that all required binaries are installed, `yarn` and `npm` are optional and only needed for projects with a `package.json`
+ */
+fn check_prerequisites() -> Result<Prerequisites> {
+    let git = which("git")
+        .map_err(|_| eyre::eyre!("git not found. Install it from: https://git-scm.com/downloads"))?;
+
+    let forge = which("forge")
+        .map_err(|_| eyre::eyre!("forge not found. Install Foundry with: curl -L https://foundry.paradigm.xyz | bash"))?;
+
+    Ok(Prerequisites {
+        git,
+        forge,
+        yarn: which("yarn").ok(),
+        npm: which("npm").ok(),
+        docker: which("docker").ok(),
+        docker_image: None,
+        git_ssh_command: None,
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<()> { 
+    // Clean up when the user presses CTRL-C instead of leaving the cloned project behind
+    interrupt::install_handler()?;
+
+    // Arguments from `--profile` are added to the ones that were passed explicitly
+    let cli_args = CliArgs::parse_from(profiles::apply_profile(env::args().collect())?);
+
+    let result = match cli_args.timeout {
+        Some(seconds) => {
+            // Subprocesses block the thread they run on, so the verification gets its own thread and the timer keeps running here
+            let runtime = tokio::runtime::Handle::current();
+            let verification = tokio::task::spawn_blocking(move || runtime.block_on(run_or_run_manifest(cli_args)));
+
+            match tokio::time::timeout(std::time::Duration::from_secs(seconds), verification).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    eprintln!("Verification timed out after {} seconds", seconds);
+                    interrupt::abort();
+                    std::process::exit(TIMEOUT_EXIT_CODE);
+                }
+            }
+        }
+        None => run_or_run_manifest(cli_args).await,
+    };
+    if interrupt::is_interrupted() {
+        interrupt::cleanup();
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+
+    result
+}
+
+async fn run_or_run_manifest(cli_args: CliArgs) -> Result<()> {
+    match cli_args.manifest.clone() {
+        Some(manifest_path) => run_manifest(Path::new(&manifest_path), cli_args).await,
+        None => run(cli_args).await,
+    }
+}
+
+/**
+ * Verifies every `[[contracts]]` entry of the manifest with its own arguments, the arguments passed explicitly take precedence.
+ * A failing entry doesn't stop the others, the run fails at the end.
+ */
+async fn run_manifest(manifest_path: &Path, cli_args: CliArgs) -> Result<()> {
+    let mut explicit = profiles::args_to_table(&cli_args.to_cli_args().into_iter().collect::<Vec<String>>());
+    explicit.remove("manifest");
+
+    let entries = load_manifest(manifest_path)?;
+    let mut failed = 0;
+    for (index, mut entry) in entries.iter().cloned().enumerate() {
+        entry.extend(explicit.clone());
+
+        let mut raw_args = vec![env::args().next().unwrap_or_default()];
+        raw_args.extend(entry.iter().filter_map(|(key, value)| profiles::table_value_to_arg(key, value)));
+        if let Err(err) = run(CliArgs::parse_from(raw_args)).await {
+            let name = entry.get("contract-name").and_then(|name| name.as_str()).unwrap_or("unnamed");
+            eprintln!("Manifest entry {} ({}): {}", index + 1, name, err);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(eyre::eyre!("{} of the {} manifest entries failed", failed, entries.len()));
+    }
+
+    Ok(())
+}
+
+async fn run(mut cli_args: CliArgs) -> Result<()> {
+    // One limiter for the whole run, so every contract and every client shares the same budget
+    let rate_limiter = match cli_args.rpc_rate_limit {
+        Some(0) => return Err(eyre::eyre!("--rpc-rate-limit must be at least 1 request per second")),
+        Some(requests_per_second) => Some(Arc::new(RateLimiter::new(requests_per_second))),
+        None => None,
+    };
+
+    // Installed first, so it also works together with the modes that return early
+    if let Some(version) = cli_args.install_solc_version.as_ref() {
+        install_solc_version(version)?;
+    }
+    if cli_args.list_solc_versions {
+        for version in list_solc_versions().await? {
+            println!("{}", version);
+        }
+        return Ok(());
+    }
+
+    // Listing the traces only needs the transaction and the RPC, so we skip prompting for everything else
+    if cli_args.list_traces || cli_args.list_traces_json || cli_args.print_trace_tree {
+        return list_traces(&cli_args, rate_limiter).await;
+    }
+    if cli_args.detect_compiler {
+        return detect_compiler(&cli_args, rate_limiter).await;
+    }
+
+    // The modes above only use the RPC, verifying needs git and forge, so make sure they are available before prompting
+    let mut prerequisites = match check_prerequisites() {
+        Ok(prerequisites) => prerequisites,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    // Exactly one way of selecting the contracts to verify may be used
+    let selections = [
+        cli_args.contract_address.is_some() || cli_args.contract_address_alias.is_some(),
+        cli_args.contract_addresses.is_some(),
+        cli_args.all_contracts,
+    ];
+    if selections.iter().filter(|selected| **selected).count() > 1 {
+        return Err(eyre::eyre!("Only one of --contract-address, --contract-addresses or --all-contracts can be used"));
+    }
+
+    // Resolve the alias before prompting, so we don't ask for an address we can look up
+    if let (Some(alias), None) = (cli_args.contract_address_alias.clone(), cli_args.contract_address.as_ref()) {
+        let client = rpc_client(
+            &cli_args.rpc.clone().ok_or_else(|| eyre::eyre!("--rpc is required to resolve --contract-address-alias"))?,
+            rate_limiter.clone(),
+        )?;
+        let chain_id = match cli_args.chain_id {
+            Some(chain_id) => chain_id,
+            None => client.get_chainid().await?.as_u64(),
+        };
+
+        let deployments_dir = cli_args.deployments_dir.clone().unwrap_or_else(|| String::from("deployments"));
+        let address = lookup_deployment_address(Path::new(&deployments_dir), chain_id, &alias)?;
+        cli_args.contract_address = Some(format!("{:?}", address));
+    }
+
+    // Collect the addresses when verifying multiple contracts, the first one stands in for `--contract-address` while prompting
+    let mut contract_addresses: Vec<String> = Vec::new();
+    if let Some(addresses) = cli_args.contract_addresses.as_ref() {
+        contract_addresses = addresses
+            .split(',')
+            .map(|address| address.trim())
+            .filter(|address| !address.is_empty())
+            .map(|address| address.to_string())
+            .collect();
+    }
+    if cli_args.all_contracts {
+        let tx_hash = cli_args.transaction.clone()
+            .ok_or_else(|| eyre::eyre!("--transaction is required to use --all-contracts"))?
+            .parse::<H256>()?;
+        let client = rpc_client(
+            &cli_args.rpc.clone().ok_or_else(|| eyre::eyre!("--rpc is required to use --all-contracts"))?,
+            rate_limiter.clone(),
+        )?;
+
+        contract_addresses = DeploymentFinder::new(Arc::new(client))
+            .find_by_tx(tx_hash)
+            .await?
+            .iter()
+            .map(|deployment| format!("{:?}", deployment.address))
+            .collect();
+    }
+    if (cli_args.all_contracts || cli_args.contract_addresses.is_some()) && contract_addresses.is_empty() {
+        return Err(eyre::eyre!("No contracts to verify"));
+    }
+    if let Some(first) = contract_addresses.first() {
+        cli_args.contract_address = Some(first.clone());
+    }
+
+    // Offline verification doesn't need the arguments that are only used to fetch the bytecode
+    if cli_args.expected_bytecode.is_some() {
+        for argument in [&mut cli_args.transaction, &mut cli_args.contract_address, &mut cli_args.rpc] {
+            if argument.is_none() {
+                *argument = Some(String::new());
+            }
+        }
+    }
+
+    // Without prompting every required argument has to be passed, `--commit` falls back to the default branch
+    if cli_args.no_interactive {
+        if cli_args.commit.is_none() {
+            cli_args.commit = Some(String::new());
+        }
+
+        let required = [
+            ("--transaction", cli_args.transaction.is_none()),
+            ("--contract-address", cli_args.contract_address.is_none()),
+            ("--git", cli_args.git.is_none()),
+            ("--contract-name", cli_args.contract_name.is_none()),
+            ("--rpc", cli_args.rpc.is_none()),
+        ];
+        let missing: Vec<&str> = required.iter().filter(|(_, missing)| *missing).map(|(flag, _)| *flag).collect();
+        if !missing.is_empty() {
+            return Err(eyre::eyre!("Missing required arguments: {}", missing.join(", ")));
+        }
+    }
+
+    let context = ();
+    let args = <Args as interactive_clap::FromCli>::from_cli(Some(cli_args.clone()), context);
+    match args {
+        ResultFromCli::Ok(interactive_args) => {
+            cli_args = interactive_args;
+        },
+        ResultFromCli::Back => {
+            return Ok(());
+        }
+        ResultFromCli::Cancel(_) => {
+            return Ok(());
+        }
+        ResultFromCli::Err(_, err) => {
+            return Err(err);
+        }
+    }
+
+    // Different forge versions can produce different bytecode, so CI may pin one
+    if let Some(version) = cli_args.install_forge_version.as_ref() {
+        let foundryup = which("foundryup")
+            .map_err(|_| eyre::eyre!("foundryup not found. Install Foundry with: curl -L https://foundry.paradigm.xyz | bash"))?;
+        run_checked(Command::new(foundryup).args(["--version", version]))?;
+    }
+    if let Some(expected) = cli_args.forge_version.as_ref() {
+        let installed = forge_version(&prerequisites)?;
+        if !installed.contains(expected.as_str()) {
+            return Err(eyre::eyre!("Expected forge version {} but found: {}", expected, installed));
+        }
+    }
+    if let Some(image) = cli_args.docker_build.clone() {
+        if prerequisites.docker.is_none() {
+            return Err(eyre::eyre!("docker not found, it is required for --docker-build. Install it from: https://docs.docker.com/get-docker/"));
+        }
+        prerequisites.docker_image = Some(image);
+    }
+    // Only the deployed code is known at a past block, the init code isn't
+    if cli_args.historical_bytecode.is_some() {
+        cli_args.deployed = true;
+    }
+    // Only `forge script` can fork, `forge inspect` and `forge build` compile without executing anything
+    if cli_args.fork_block.is_some() {
+        if cli_args.use_forge_script.is_none() {
+            return Err(eyre::eyre!("--fork-block requires --use-forge-script"));
+        }
+        if cli_args.rpc.is_none() {
+            return Err(eyre::eyre!("--rpc is required to use --fork-block"));
+        }
+    }
+    // Only the commands that clone or fetch get the ssh options, without the flag the environment is left as it is
+    prerequisites.git_ssh_command = cli_args
+        .git_ssh_strict_host_checking
+        .map(|checking| format!("ssh -o StrictHostKeyChecking={}", checking));
+
+    let mut results: Vec<VerifyResult> = Vec::new();
+    if contract_addresses.is_empty() {
+        results.push(verify(&prerequisites, cli_args.clone(), rate_limiter.clone()).await?);
+    }
+
+    // Every contract is verified against the same source and transaction
+    for address in contract_addresses {
+        let mut contract_args = cli_args.clone();
+        contract_args.contract_address = Some(address);
+        results.push(verify(&prerequisites, contract_args, rate_limiter.clone()).await?);
+    }
+
+    // The diamond itself only routes calls, the code that runs is in its facets
+    if cli_args.check_diamond_facets {
+        verify_diamond_facets(&prerequisites, &cli_args, rate_limiter.clone()).await?;
+    }
+
+    // The CSV has a row per contract, so it is only printed once all of them are verified
+    if !cli_args.ci && cli_args.output == Some(OutputFormat::Csv) {
+        print!("{}", format_csv(&results)?);
+    }
+
+    // In CI a mismatch has to fail the pipeline, but only after every contract has been verified and reported
+    let mismatches = results.iter().filter(|result| !result.matched).count();
+    if cli_args.ci && mismatches > 0 {
+        return Err(eyre::eyre!("{} of the {} contracts did not match", mismatches, results.len()));
+    }
+
+    // In strict mode anything that needs a manual review blocks the pipeline, the reports have been written by now
+    let warning_count: usize = results.iter().map(|result| result.warnings.len()).sum();
+    if cli_args.fail_on_warning && warning_count > 0 {
+        return Err(eyre::eyre!("--fail-on-warning is set and {} warning(s) were reported", warning_count));
+    }
+
+    Ok(())
+}
+
+/**
+ * Verifies every facet of the diamond at `--contract-address`, fails if a facet is missing from the manifest or doesn't match
+ */
+async fn verify_diamond_facets(prerequisites: &Prerequisites, cli_args: &CliArgs, rate_limiter: Option<Arc<RateLimiter>>) -> Result<()> {
+    let manifest_path = cli_args.facets_manifest.clone()
+        .ok_or_else(|| eyre::eyre!("--check-diamond-facets requires --facets-manifest"))?;
+    let manifest = FacetsManifest::load(Path::new(&manifest_path))?;
+    let diamond = cli_args.contract_address.clone().unwrap_or_default().parse::<Address>()?;
+    let rpc = cli_args.rpc.clone().ok_or_else(|| eyre::eyre!("--rpc is required to read the facets of a diamond"))?;
+    let client = rpc_client(&rpc, rate_limiter.clone())?;
+
+    let results = DiamondFacetVerifier::new(prerequisites, cli_args.clone(), rate_limiter)
+        .verify_all(&client, diamond, &manifest)
+        .await?;
+    for result in results.iter() {
+        eprintln!("Facet {}", result);
+    }
+
+    let unmatched = results.iter().filter(|result| !result.matched()).count();
+    if unmatched > 0 {
+        return Err(eyre::eyre!("{} of the {} facets of {:?} could not be verified", unmatched, results.len(), diamond));
+    }
+
+    Ok(())
+}
+
+/**
+ * Verifies a single contract, all arguments have been resolved or prompted for
+ */
+async fn verify(prerequisites: &Prerequisites, cli_args: CliArgs, rate_limiter: Option<Arc<RateLimiter>>) -> Result<VerifyResult> {
+    // `--ci` is a shorthand for `--no-spinner --output json --quiet`
+    let output_format = if cli_args.ci { OutputFormat::Json } else { cli_args.output.unwrap_or(OutputFormat::Text) };
+    let quiet = cli_args.ci || cli_args.quiet;
+    // The proof is printed on stdout, so it can't share it with another machine readable format
+    if cli_args.prove && output_format != OutputFormat::Text {
+        return Err(eyre::eyre!("--prove can not be combined with --output {} or --ci", output_format));
+    }
+    if cli_args.format_output.is_some() && (cli_args.prove || output_format != OutputFormat::Text) {
+        return Err(eyre::eyre!("--format-output can not be combined with --output, --ci or --prove"));
+    }
+    let signing_key = match (cli_args.prove, cli_args.signing_key.as_ref()) {
+        (true, Some(path)) => Some(load_signing_key(Path::new(path))?),
+        (true, None) => return Err(eyre::eyre!("--prove requires --signing-key")),
+        (false, _) => None,
+    };
+
+    let logger = Logger {
+        to_stderr: output_format != OutputFormat::Text || cli_args.prove || cli_args.format_output.is_some(),
+        quiet,
+    };
+
+    logger.info(&format!(
+        "Your arguments:  {}",
+        shell_words::join(cli_args.to_cli_args())
+    ));
+
+    // Keep the arguments around for reporting
+    let cli_args_used: Vec<String> = cli_args.to_cli_args().into_iter().collect();
+
+    if let Some(name) = cli_args.save_profile.as_ref() {
+        profiles::save_profile(name, &cli_args_used)?;
+        logger.info(&format!("Saved the arguments as profile {}", name));
+    }
+    // `src/MyContract.sol:MyContract` selects one of several contracts with the same name
+    let contract_ref = validate_contract_name(&cli_args.contract_name.clone().unwrap())?;
+    let git_url = match cli_args.git.clone().unwrap() {
+        url if cli_args.sanitize_git_url => sanitize_git_url(&url)?,
+        url => url,
+    };
+
+    // With the expected bytecode we never talk to an RPC, so the transaction and the address are only informational
+    let offline = cli_args.expected_bytecode.is_some();
+
+    // The deployment transaction
+    let tx_hash = match cli_args.clone().transaction.unwrap() {
+        hash if hash.is_empty() && offline => H256::zero(),
+        hash => hash.parse::<H256>()?,
+    };
+
+    // The contract to verify
+    let contract = match cli_args.contract_address.clone().unwrap() {
+        address if address.is_empty() && offline => Address::zero(),
+        address => address.parse::<Address>()?,
+    };
+
+    // Build the RPC client, all requests share the rate limit
+    let client = if offline {
+        None
+    } else {
+        Some(Arc::new(rpc_client(&cli_args.rpc.clone().unwrap(), rate_limiter.clone())?))
+    };
+    let online = || client.clone().ok_or_else(|| eyre::eyre!("An RPC is required for this, it can not be used with --expected-bytecode"));
+
+    // Could be set to Some("") instead of None, if thats the case we force it to be None
+    let mut commit: Option<String> = None;
+    if let Some(hash) = cli_args.commit{
+        if !hash.is_empty() {
+            commit = Some(hash);
+        }
+    }
+
+    let fuzzy_match = match cli_args.fuzzy_match.as_deref() {
+        Some(threshold) => match threshold.parse::<f64>() {
+            Ok(threshold) if (0.0..=1.0).contains(&threshold) => Some(threshold),
+            _ => return Err(eyre::eyre!("--fuzzy-match must be between 0.0 and 1.0")),
+        },
+        None => None,
+    };
+
+    // Verifying against a floating branch head is not reproducible
+    if cli_args.require_commit && commit.is_none() {
+        return Err(eyre::eyre!("--require-commit is set but no --commit was provided"));
+    }
+
+    // `--git-tag` and `--git-branch` are explicit alternatives to `--commit`
+    let git_ref = match (commit.clone(), cli_args.git_tag.clone(), cli_args.git_branch.clone()) {
+        (None, None, None) => None,
+        (Some(reference), None, None) => Some(GitRef::new(&reference, cli_args.ref_type.unwrap_or(RefType::Auto))),
+        (None, Some(tag), None) => Some(GitRef::Tag(tag)),
+        (None, None, Some(branch)) => Some(GitRef::Branch(branch)),
+        _ => return Err(eyre::eyre!("Only one of --commit, --git-tag and --git-branch can be set")),
+    };
+
+    // Make sure the commit can be checked out before we spend time cloning
+    if cli_args.anchor_commit {
+        if let Some(hash) = commit.clone() {
+            if !verify_commit_exists(prerequisites, &git_url, &hash)? {
+                return Err(eyre::eyre!(
+                    "Commit {} does not exist in {}, it may have been force-pushed away",
+                    hash,
+                    git_url
+                ));
+            }
+        }
+    }
+
+    // An explicit chain ID takes precedence over the one reported by the RPC
+    let rpc_chain_id = match client.as_ref() {
+        Some(client) => client.get_chainid().await?.as_u64(),
+        None => cli_args.chain_id.unwrap_or_default(),
+    };
+    let chain_id = cli_args.chain_id.unwrap_or(rpc_chain_id);
+    let mut warnings: Vec<String> = Vec::new();
+    if chain_id != rpc_chain_id {
+        warnings.push(format!(
+            "The RPC reports chain ID {} but --chain-id is set to {}",
+            rpc_chain_id,
+            chain_id
+        ));
+    }
+
+    // The spinner uses ANSI escape codes, only use it when we are writing to a terminal
+    let use_spinner = !cli_args.no_spinner && !cli_args.ci && std::io::stdout().is_terminal();
+    let mut spinner: Progress;
+
+    let trace_bytecode: String;
+    let mut trace_deployed_code: Option<Bytes> = None;
+    if let Some(expected_bytecode) = cli_args.expected_bytecode.as_ref() {
+        spinner = Progress::new(use_spinner, quiet, "Using the expected bytecode");
+
+        // Normalize to the format of the traced bytecode
+        let bytecode = decode_bytecode(expected_bytecode)
+            .ok_or_else(|| eyre::eyre!("--expected-bytecode is not valid hex"))?;
+        trace_bytecode = Bytes::from(bytecode).to_string();
+    } else if let Some(block) = cli_args.historical_bytecode {
+        spinner = Progress::new(use_spinner, quiet, &format!("Fetching the code at block {}", block));
+
+        // Whatever code was running at that block, e.g. before an upgrade replaced it
+        let code = online()?.get_code(contract, Some(block.into())).await?;
+        if code.is_empty() {
+            return Err(eyre::eyre!("{:?} had no code at block {}", contract, block));
+        }
+        trace_bytecode = code.to_string();
+        trace_deployed_code = Some(code);
+    } else if cli_args.simulate {
+        spinner = Progress::new(use_spinner, quiet, "Simulating the deployment");
+
+        // Simulating returns the deployed bytecode instead of the init code
+        let calldata = cli_args.calldata.as_ref()
+            .ok_or_else(|| eyre::eyre!("--calldata is required in --simulate mode"))?;
+        let calldata = hex::decode(calldata.trim_start_matches("0x"))?;
+
+        trace_bytecode = simulate_deployment(online()?.as_ref(), &calldata).await?.to_string();
+    } else {
+        spinner = Progress::new(use_spinner, quiet, "Fetching traces from the transaction");
+
+        // Find the `CREATE` call of the contract in the transaction
+        let finder = DeploymentFinder::new(online()?)
+            .with_trace_timeout(cli_args.trace_timeout)
+            .with_trace_provider(
+                cli_args.trace_provider_type.unwrap_or(TraceProvider::Auto),
+                cli_args.rpc.clone().filter(|_| cli_args.cache_trace_provider),
+            )
+            .with_max_depth(cli_args.max_trace_depth.map(|depth| depth as usize));
+        let tx_from_address = match cli_args.tx_from_address.as_ref() {
+            Some(address) => Some(address.parse::<Address>()?),
+            None => None,
+        };
+
+        let traces = finder.trace_transaction(tx_hash).await?;
+        if let Some(path) = cli_args.save_trace_output.as_ref() {
+            std::fs::write(path, serde_json::to_string_pretty(&traces)?)?;
+        }
+
+        let deployments: Vec<ContractDeployment> = finder
+            .deployments(&traces)
+            .into_iter()
+            .filter(|deployment| deployment.address == contract)
+            .filter(|deployment| tx_from_address.is_none_or(|deployer| deployment.deployer == deployer))
+            .collect();
+
+        // The number of items matching should only be more than `1` if a factory deployed the same contract twice,
+        // in that case `--trace-index` selects which one to use
+        if deployments.len() != 1 && cli_args.trace_index.is_none() {
+            // TODO: Error
+            warnings.push(format!(
+                "An unexpected amount of traces were found, {} traces found. Use --trace-index to select one (see --list-traces)",
+                deployments.len()
+            ));
+        }
+
+        let trace_index = cli_args.trace_index.unwrap_or(0);
+        let deployment = deployments.get(trace_index as usize).ok_or_else(|| match deployments.len() {
+            0 => eyre::eyre!("Could not find trace!"),
+            found => eyre::eyre!("--trace-index {} is out of range, {} traces found", trace_index, found),
+        })?;
+        trace_bytecode = if cli_args.deployed { deployment.deployed_code.to_string() } else { deployment.init_code.to_string() };
+        trace_deployed_code = Some(deployment.deployed_code.clone());
+    }
+
+    spinner.update("Cloning project and installing dependencies");
+
+    // Get a temp folder where we can clone the project to
+    let tmp_folder = &mut env::temp_dir();
+    tmp_folder.push(&contract_ref.name);
+
+    // Clone and configure the project
+    let git_cache = if cli_args.git_cache { Some(GitWorktreeCache::new(&prerequisites.git)?.with_ssh_command(prerequisites.git_ssh_command.clone())) } else { None };
+    let project_path = configure_project(prerequisites, tmp_folder, git_url.clone(), git_ref.clone(), git_cache.as_ref()).await?;
+
+    // Put the config overrides in their own profile so the project's profiles are left untouched
+    let mut forge_profile: Option<&str> = None;
+    if let Some(overrides) = cli_args.foundry_config_override.as_ref() {
+        inject_foundry_profile(&project_path, &parse_config_overrides(overrides)?)?;
+        forge_profile = Some(BYTEMATCH_PROFILE);
+    }
+
+    // Pass the remappings forge detects explicitly, so every `forge inspect` resolves the imports the same way
+    let remappings = if cli_args.forge_remappings_auto {
+        forge_remappings(prerequisites, &project_path, forge_profile)?
+    } else {
+        Vec::new()
+    };
+
+    spinner.update("Compiling contract");
+    // forge takes the qualified name, artifacts and folders are named after the contract only
+    let contract_name = contract_ref.to_string();
+    let contract_path = cli_args.contract_path.clone().or_else(|| contract_ref.path.clone());
+
+    // Ape projects are compiled with `ape`, Vyper contracts with `vyper`, everything else with `forge`
+    let compiler = match (cli_args.backend, cli_args.hardhat_config_path.as_ref()) {
+        (Some(backend), _) => backend,
+        (None, Some(_)) => Compiler::Hardhat,
+        (None, None) => Compiler::detect(&project_path, contract_path.as_deref()),
+    };
+
+    // A simulated deployment returns the deployed bytecode, so we compare against that instead of the init code
+    let compare_deployed = cli_args.simulate || cli_args.deployed;
+    let artifact_field = if compare_deployed { "deployedBytecode" } else { "bytecode" };
+
+    let compile_bytecode: String;
+    // Only known when it is cheap to get or needed for `--cross-verify`
+    let compile_deployed_bytecode: Option<String>;
+    let compiled_solc_version: Option<String>;
+    match compiler {
+        Compiler::Forge => {
+            // Show the compiler version before the (possibly long) compilation
+            if cli_args.print_solc_version {
+                match configured_solc_version(&project_path, forge_profile) {
+                    Some(version) => logger.info(&format!("Using solc {} (from foundry.toml)", version)),
+                    None => logger.info("Using the solc version that matches the pragmas (Foundry default)"),
+                }
+            }
+
+            // A deployment script sets up the compilation itself, so its broadcast holds the bytecode
+            if let Some(script) = cli_args.use_forge_script.as_ref() {
+                if compare_deployed {
+                    return Err(eyre::eyre!("--use-forge-script compares the init code, it can't be combined with --simulate or --deployed"));
+                }
+                let fork = cli_args.fork_block.and_then(|block| cli_args.rpc.as_deref().map(|rpc| (rpc, block)));
+                compile_bytecode = forge_script_bytecode(prerequisites, &project_path, forge_profile, script, &contract_ref.name, fork)?;
+            } else {
+                // Use forge inspect to build the bytecode and get the result
+                let mut inspect_args = vec!["inspect", "--force", contract_name.as_str(), artifact_field];
+                if cli_args.foundry_json_output {
+                    inspect_args.push("--json");
+                }
+                let mut inspect_command = forge_command(prerequisites, &project_path, forge_profile);
+                inspect_command.args(inspect_args);
+                for remapping in &remappings {
+                    inspect_command.args(["--remappings", remapping]);
+                }
+                let compile_output = run_command(&mut inspect_command)?;
+
+                // Keep the raw output before anything is parsed out of it
+                if let Some(path) = cli_args.save_forge_output.as_ref() {
+                    save_command_output(Path::new(path), &compile_output)?;
+                }
+                let compile_output = check_output(&inspect_command, compile_output)?;
+
+                compile_bytecode = match str::from_utf8(&compile_output.stdout) {
+                    Ok(v) if cli_args.foundry_json_output => parse_forge_inspect_json(v)?,
+                    Ok(v) => v.trim().to_string(),
+                    Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
+                };
+            }
+
+            compile_deployed_bytecode = if compare_deployed {
+                Some(compile_bytecode.clone())
+            } else if cli_args.cross_verify.is_some() {
+                Some(forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "deployedBytecode")?)
+            } else {
+                None
+            };
+
+            // Get the compiler version that was used for our compilation
+            let metadata_output = forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "metadata")?;
+            compiled_solc_version = parse_compiler_version(&metadata_output);
+        }
+        Compiler::Vyper => {
+            let vyper = match cli_args.vyper_path.clone() {
+                Some(path) => PathBuf::from(path),
+                None => which("vyper").map_err(|_| eyre::eyre!("vyper not found. Install it with: pip install vyper"))?,
+            };
+            let contract_path = contract_path.clone()
+                .ok_or_else(|| eyre::eyre!("--contract-path is required to compile Vyper contracts"))?;
+
+            let vyper_output = compile_vyper(&vyper, &project_path, &contract_path)?;
+            compile_deployed_bytecode = Some(vyper_output.bytecode_runtime.clone());
+            compile_bytecode = if compare_deployed { vyper_output.bytecode_runtime } else { vyper_output.bytecode };
+            compiled_solc_version = None;
+        }
+        Compiler::Ape => {
+            let bytecode = compile_ape(&project_path, &contract_ref.name)?;
+            let runtime_bytecode = read_ape_artifact(&project_path, &contract_ref.name, "runtimeBytecode")?;
+            compile_bytecode = if compare_deployed { runtime_bytecode.clone() } else { bytecode };
+            compile_deployed_bytecode = Some(runtime_bytecode);
+            compiled_solc_version = None;
+        }
+        Compiler::Hardhat => {
+            let network = cli_args.hardhat_network_name.clone().unwrap_or_else(|| DEFAULT_HARDHAT_NETWORK.to_string());
+            let config = hardhat_config(&project_path, cli_args.hardhat_config_path.as_deref())?;
+            compile_hardhat(&project_path, &config, &network)?;
+
+            // Paths in the config, like the artifacts directory, are relative to the directory of the config
+            let hardhat_root = project_path.join(&config).parent().map(Path::to_path_buf).unwrap_or_else(|| project_path.clone());
+            let bytecode = read_hardhat_artifact(&hardhat_root, &contract_ref.name, contract_path.as_deref(), "bytecode")?;
+            let deployed_bytecode = read_hardhat_artifact(&hardhat_root, &contract_ref.name, contract_path.as_deref(), "deployedBytecode")?;
+            compile_bytecode = if compare_deployed { deployed_bytecode.clone() } else { bytecode };
+            compile_deployed_bytecode = Some(deployed_bytecode);
+            compiled_solc_version = None;
+        }
+    }
+    // Dump both bytecodes as they are, for external analysis tools
+    if let Some(path) = cli_args.output_init_bytecode.as_ref() {
+        std::fs::write(path, &trace_bytecode)?;
+    }
+    if let Some(path) = cli_args.output_compiled_init_bytecode.as_ref() {
+        std::fs::write(path, &compile_bytecode)?;
+    }
+
+    // What went into the build, for supply chain audits
+    if let Some(path) = cli_args.generate_sbom.as_ref() {
+        if compiler != Compiler::Forge {
+            return Err(eyre::eyre!("--generate-sbom is only supported for forge projects"));
+        }
+        let metadata = forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "metadata")?;
+        let bom = generate_sbom(&prerequisites.git, &project_path, &ForgeArtifact::from_metadata(&contract_ref.name, &metadata)?)?;
+        save_sbom(Path::new(path), &bom)?;
+        logger.info(&format!("Saved the SBOM to {}", path));
+    }
+
+    // Code over the EIP-3860 and EIP-170 limits can't be deployed (again), e.g. on another chain
+    let mut code_sizes: Vec<CodeSize> = Vec::new();
+    if compare_deployed {
+        code_sizes.extend(decode_bytecode(&compile_bytecode).map(|code| CodeSize::deployed_code("compiled", &code)));
+        code_sizes.extend(decode_bytecode(&trace_bytecode).map(|code| CodeSize::deployed_code("on-chain", &code)));
+    } else {
+        code_sizes.extend(decode_bytecode(&compile_bytecode).map(|code| CodeSize::init_code("compiled", &code)));
+        code_sizes.extend(decode_bytecode(&trace_bytecode).map(|code| CodeSize::init_code("on-chain", &code)));
+        code_sizes.extend(compile_deployed_bytecode.as_deref().and_then(decode_bytecode).map(|code| CodeSize::deployed_code("compiled", &code)));
+        code_sizes.extend(trace_deployed_code.as_ref().map(|code| CodeSize::deployed_code("on-chain", code)));
+    }
+    for code_size in code_sizes.iter() {
+        if cli_args.verbose {
+            logger.info(&format!("Size of the {}", code_size));
+        }
+        if code_size.exceeds_limit() {
+            if cli_args.check_constructor_bytecode_size {
+                return Err(eyre::eyre!("The {} exceeds the limit", code_size));
+            }
+            warnings.push(format!("The {} exceeds the limit", code_size));
+        }
+    }
+
+    let mut compile_bytecode = compile_bytecode;
+    let mut trace_bytecode = trace_bytecode;
+
+    // Immutables are only filled in by the constructor, so their values in the deployed code always differ from the compiled code
+    if cli_args.deployed && compiler == Compiler::Forge {
+        let refs = read_immutable_references(&project_path, &contract_ref.name, contract_path.as_deref())?;
+        if let (Some(mut compiled), Some(mut onchain)) = (decode_bytecode(&compile_bytecode), decode_bytecode(&trace_bytecode)) {
+            let mut values: Vec<(String, Vec<u8>)> = extract_immutable_values(&onchain, &refs).into_iter().collect();
+            values.sort();
+            for (id, value) in values {
+                logger.info(&format!("Immutable (AST id {}): 0x{}", id, hex::encode(value)));
+            }
+
+            zero_immutables(&mut compiled, &refs);
+            zero_immutables(&mut onchain, &refs);
+            compile_bytecode = Bytes::from(compiled).to_string();
+            trace_bytecode = Bytes::from(onchain).to_string();
+        }
+    }
+
+    let compile_init = remove_metadata(compile_bytecode.clone());
+
+    let trace_init = remove_metadata(trace_bytecode.clone());
+
+    spinner.stop();
+
+    // The number of differing bytes is reported whenever a threshold is set, even if it is not used
+    if let Some(threshold) = cli_args.diff_threshold {
+        logger.info(&format!("{} bytes differ (threshold {})", differing_bytes(&compile_init, &trace_init), threshold));
+    }
+
+    // Compare the two results
+    let match_kind = match_bytecodes(&compile_init, &trace_init, &MatchOptions {
+        accept_partial_match: cli_args.accept_partial_match,
+        fuzzy_match,
+        diff_threshold: cli_args.diff_threshold.map(|threshold| threshold as usize),
+    });
+    let matched = match_kind != MatchKind::NoMatch;
+    logger.result(&match_kind.to_string());
+
+    // A different metadata usually only means a different build machine or path, a different body means different code
+    if cli_args.compare_metadata_only {
+        let compiled_metadata = decode_metadata(&compile_bytecode);
+        let traced_metadata = decode_metadata(&trace_bytecode);
+
+        if compile_init != trace_init {
+            let first_difference = compile_init.chars().zip(trace_init.chars()).position(|(a, b)| a != b)
+                .unwrap_or_else(|| compile_init.len().min(trace_init.len()));
+            logger.info(&format!(
+                "Bytecodes differ in the main body, starting at byte {}",
+                first_difference.saturating_sub(2) / 2
+            ));
+        } else if compiled_metadata == traced_metadata {
+            logger.info("Bytecodes match, including the metadata");
+        } else {
+            logger.info("Bytecodes match except for the metadata section");
+            logger.info(&format!("Compiled metadata: {}", serde_json::to_string(&compiled_metadata)?));
+            logger.info(&format!("On-chain metadata: {}", serde_json::to_string(&traced_metadata)?));
+        }
+    }
+
+    // A different compiler version is suspicious, especially if the bytecode matched
+    if let (Some(onchain_version), Some(compiled_version)) = (extract_solc_version(&trace_bytecode), compiled_solc_version.clone()) {
+        if onchain_version != compiled_version {
+            warnings.push(format!(
+                "On-chain contract was compiled with solc {} but we compiled with {}",
+                onchain_version,
+                compiled_version
+            ));
+        }
+    }
+
+    // The on-chain bytecode might be a well known contract
+    if let Some(db_path) = cli_args.known_contracts_db.as_ref() {
+        let db = KnownContractsDb::load(Path::new(db_path))?;
+        if let Some(name) = decode_bytecode(&trace_init).and_then(|bytecode| db.lookup(&bytecode).cloned()) {
+            logger.info(&format!("Bytecode matches known contract: {}", name));
+        }
+    }
+
+    // The events emitted during the deployment should all be part of the compiled ABI
+    if cli_args.compare_events {
+        if compiler != Compiler::Forge {
+            return Err(eyre::eyre!("--compare-events is only supported for contracts compiled with forge"));
+        }
+
+        let receipt = online()?.get_transaction_receipt(tx_hash).await?
+            .ok_or_else(|| eyre::eyre!("Could not find the receipt of transaction {:?}", tx_hash))?;
+        let logs: Vec<Log> = receipt.logs.into_iter().filter(|log| log.address == contract).collect();
+
+        let abi: Abi = serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?)?;
+        let abi_events: Vec<AbiEvent> = abi.events().cloned().collect();
+
+        let report = match_event_signatures(&logs, &abi_events);
+        for event in report.matched.iter() {
+            logger.info(&format!("Emitted event: {}", event));
+        }
+        for topic in report.unknown_topics.iter() {
+            warnings.push(format!("Emitted event {:?} is not part of the compiled ABI", topic));
+        }
+        for event in report.not_emitted.iter() {
+            logger.info(&format!("Event was not emitted during deployment: {}", event));
+        }
+    }
+
+    // The ABI is already compiled, so exporting the signatures is cheap
+    if let Some(path) = cli_args.export_function_sigs.as_ref() {
+        if compiler != Compiler::Forge {
+            return Err(eyre::eyre!("--export-function-sigs is only supported for contracts compiled with forge"));
+        }
+
+        let abi: Abi = serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?)?;
+        SignatureExport::from_abi(&abi).save(Path::new(path))?;
+        logger.info(&format!("Wrote the function and event signatures to {}", path));
+    }
+
+    // Missing or changed documentation doesn't change the bytecode, but is worth knowing about in a documentation audit
+    if cli_args.compare_natspec {
+        if compiler != Compiler::Forge {
+            return Err(eyre::eyre!("--compare-natspec is only supported for contracts compiled with forge"));
+        }
+
+        let abi: Abi = serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?)?;
+        let compiled = NatSpec {
+            devdoc: serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "devdoc")?)?,
+            userdoc: serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "userdoc")?)?,
+        };
+        let expected: Option<NatSpec> = match cli_args.expected_natspec.as_ref() {
+            Some(path) => Some(serde_json::from_str(&std::fs::read_to_string(path)
+                .map_err(|err| eyre::eyre!("Could not read {}: {}", path, err))?)?),
+            None => None,
+        };
+
+        let report = NatSpecReport::check(&abi, &compiled, expected.as_ref());
+        for signature in report.undocumented.iter() {
+            warnings.push(format!("Function {} has no NatSpec documentation", signature));
+        }
+        if let Some(diff) = report.diff.as_ref() {
+            for path in diff.missing.iter() {
+                warnings.push(format!("NatSpec {} is expected but missing", path));
+            }
+            for path in diff.added.iter() {
+                warnings.push(format!("NatSpec {} is not in the expected documentation", path));
+            }
+            for path in diff.changed.iter() {
+                warnings.push(format!("NatSpec {} differs from the expected documentation", path));
+            }
+            if diff.is_empty() {
+                logger.info("The NatSpec documentation matches the expected documentation");
+            }
+        }
+    }
+
+    // The source files should be exactly the ones the on-chain metadata was generated from
+    if cli_args.check_source_hashes {
+        let metadata = decode_metadata(&trace_bytecode)
+            .ok_or_else(|| eyre::eyre!("Could not find the metadata in the on-chain bytecode"))?;
+        let gateway = cli_args.ipfs_gateway.clone().unwrap_or_else(|| DEFAULT_IPFS_GATEWAY.to_string());
+
+        let swarm_gateway = cli_args.fetch_swarm
+            .then(|| cli_args.swarm_gateway.clone().unwrap_or_else(|| DEFAULT_SWARM_GATEWAY.to_string()));
+
+        let report = verify_source_hashes(&project_path, &metadata, &gateway, swarm_gateway.as_deref()).await?;
+        logger.info(&format!("{} source files match the metadata", report.matched.len()));
+        for source in report.mismatched.iter() {
+            warnings.push(format!("Source file {} does not match the hash in the metadata", source));
+        }
+        for source in report.missing.iter() {
+            warnings.push(format!("Source file {} from the metadata is missing", source));
+        }
+    }
+
+    // A branch or the default branch is expected to have moved on since the deployment, only a pinned commit or tag is checked
+    let check_commit_timing = !cli_args.simulate
+        && client.is_some()
+        && matches!(git_ref, Some(GitRef::Commit(_)) | Some(GitRef::Tag(_)));
+
+    // The transaction is fetched once for everything that needs it
+    let transaction = if check_commit_timing || cli_args.verify_nonce || cli_args.check_proxy_admin.is_some() {
+        Some(online()?.get_transaction(tx_hash).await?
+            .ok_or_else(|| eyre::eyre!("Could not find transaction {:?}", tx_hash))?)
+    } else {
+        None
+    };
+
+    // The source can't have been written after the contract was deployed
+    if let (true, Some(client), Some(transaction)) = (check_commit_timing, client.as_ref(), transaction.as_ref()) {
+        let block_number = transaction.block_number
+            .ok_or_else(|| eyre::eyre!("Transaction {:?} is not mined", tx_hash))?;
+        let block = client.get_block(block_number).await?
+            .ok_or_else(|| eyre::eyre!("Could not fetch block {}", block_number))?;
+        let deploy_ts = Utc.timestamp_opt(block.timestamp.as_u64() as i64, 0)
+            .single()
+            .ok_or_else(|| eyre::eyre!("Invalid timestamp of block {}", block_number))?;
+        let commit_ts = commit_timestamp(&prerequisites.git, &project_path)?;
+
+        if let CommitTimingStatus::PostdatesDeployment { delta } = verify_commit_predates_deployment(commit_ts, deploy_ts) {
+            warnings.push(format!(
+                "The commit was made {} after the deployment ({} vs {}), the source may have been modified after deploying",
+                format_duration(delta),
+                commit_ts.to_rfc3339(),
+                deploy_ts.to_rfc3339()
+            ));
+        }
+    }
+
+    // An upgrade may only append to the storage layout of the previous version
+    let mut storage_layouts: Option<(StorageLayout, StorageLayout)> = None;
+    if cli_args.compare_storage_layout {
+        let previous_commit = cli_args.previous_commit.clone()
+            .ok_or_else(|| eyre::eyre!("--compare-storage-layout requires --previous-commit"))?;
+        let previous_git = match cli_args.previous_git.clone() {
+            Some(url) if cli_args.sanitize_git_url => sanitize_git_url(&url)?,
+            Some(url) => url,
+            None => git_url.clone(),
+        };
+
+        let previous_folder = &mut env::temp_dir();
+        previous_folder.push(format!("{}-previous", contract_ref.name));
+        let previous_path = configure_project(
+            prerequisites,
+            previous_folder,
+            previous_git,
+            Some(GitRef::new(&previous_commit, RefType::Auto)),
+            git_cache.as_ref(),
+        ).await?;
+
+        let previous_remappings = if cli_args.forge_remappings_auto {
+            forge_remappings(prerequisites, &previous_path, None)?
+        } else {
+            Vec::new()
+        };
+
+        let old: StorageLayout = serde_json::from_str(&forge_inspect(prerequisites, &previous_path, None, &previous_remappings, &contract_name, "storage-layout")?)?;
+        let new: StorageLayout = serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "storage-layout")?)?;
+        let diff = StorageLayoutDiff::compute(&old, &new);
+
+        for entry in diff.added.iter() {
+            logger.info(&format!("Storage variable added: {}", entry));
+        }
+        for entry in diff.removed.iter() {
+            warnings.push(format!("Storage variable removed: {}", entry));
+        }
+        for (old_entry, new_entry) in diff.changed.iter() {
+            warnings.push(format!("Storage variable changed: {} -> {}", old_entry, new_entry));
+        }
+        if diff.is_upgrade_safe() {
+            logger.info("The storage layout is compatible with the previous version");
+        }
+        storage_layouts = Some((old, new));
+    }
+
+    // A proxy that keeps its own state in sequential slots overwrites the state of its implementation
+    if let Some(proxy_name) = cli_args.check_storage_collision.as_ref() {
+        if compiler != Compiler::Forge {
+            return Err(eyre::eyre!("--check-storage-collision is only supported with forge"));
+        }
+        let proxy_name = validate_contract_name(proxy_name)?.to_string();
+
+        let proxy_layout: StorageLayout = serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &proxy_name, "storage-layout")?)?;
+        let impl_layout: StorageLayout = serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "storage-layout")?)?;
+        let collisions = StorageCollisionChecker::check(&proxy_layout, &impl_layout);
+
+        for collision in collisions.iter() {
+            warnings.push(format!("Critical: storage collision, {}", collision));
+        }
+        if collisions.is_empty() {
+            logger.info(&format!("The storage of {} does not collide with the storage of {}", proxy_name, contract_name));
+        }
+    }
+
+    // The pinned dependencies should be the releases they claim to be
+    if cli_args.check_dependencies {
+        let report = check_dependency_versions(&prerequisites.git, &project_path)?;
+        for dependency in report.dependencies.iter() {
+            logger.info(&format!(
+                "Dependency {}: version {}, tag {}",
+                dependency.name,
+                dependency.version.as_deref().unwrap_or("unknown"),
+                dependency.git_tag.as_deref().unwrap_or("none")
+            ));
+        }
+        for dependency in report.inconsistent() {
+            warnings.push(format!(
+                "Dependency {} is pinned to tag {} but its package.json says version {}",
+                dependency.name,
+                dependency.git_tag.as_deref().unwrap_or_default(),
+                dependency.version.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    // The constructor must never delegate to other code, even if the runtime code does
+    if cli_args.strict_constructor {
+        if cli_args.deployed {
+            return Err(eyre::eyre!("--strict-constructor checks the init code, it can not be used with --deployed"));
+        }
+        let init_code = decode_bytecode(&trace_bytecode)
+            .ok_or_else(|| eyre::eyre!("Could not decode the traced init code"))?;
+        let deployed_code = trace_deployed_code.clone()
+            .ok_or_else(|| eyre::eyre!("--strict-constructor requires the deployment trace, it can not be used with --simulate"))?;
+
+        let constructor = BytecodeAnalyzer::new(constructor_code(&init_code, &deployed_code));
+        if constructor.has_delegatecall() {
+            return Err(eyre::eyre!("The constructor contains a DELEGATECALL"));
+        }
+        logger.info("The constructor contains no DELEGATECALL");
+    }
+
+    // The constructor can be reentered through any external call it makes
+    if cli_args.check_no_external_calls_in_constructor {
+        if cli_args.deployed {
+            return Err(eyre::eyre!("--check-no-external-calls-in-constructor checks the init code, it can not be used with --deployed"));
+        }
+        let init_code = decode_bytecode(&trace_bytecode)
+            .ok_or_else(|| eyre::eyre!("Could not decode the traced init code"))?;
+        let deployed_code = trace_deployed_code.clone()
+            .ok_or_else(|| eyre::eyre!("--check-no-external-calls-in-constructor requires the deployment trace, it can not be used with --simulate"))?;
+
+        let calls = find_calls_in_constructor(&init_code, &deployed_code);
+        for offset in calls.iter() {
+            warnings.push(format!(
+                "The constructor contains a {} at offset {}",
+                opcode_name(init_code[*offset]).unwrap_or("call"),
+                offset
+            ));
+        }
+        if calls.is_empty() {
+            logger.info("The constructor makes no external calls");
+        }
+    }
+
+    // Show how much the bytecodes differ and where
+    if cli_args.diff_stats {
+        if let (Some(compiled), Some(traced)) = (decode_bytecode(&compile_bytecode), decode_bytecode(&trace_bytecode)) {
+            let stats = BytecodeDiffStats::compute(&compiled, &traced);
+            logger.info(&format!("Differing bytes: {} ({:.2}%)", stats.differing_bytes, stats.difference_percentage));
+            logger.info(&format!("Longest common subsequence: {} bytes", stats.lcs_length));
+            logger.info(&format!(
+                "Changes per section: constructor {}, body {}, metadata {}",
+                stats.constructor_changes,
+                stats.body_changes,
+                stats.metadata_changes
+            ));
+        }
+    }
+
+    // Show what changed at the opcode level
+    if cli_args.output_opcodes_diff && !matched {
+        if let (Some(compiled), Some(traced)) = (decode_bytecode(&compile_init), decode_bytecode(&trace_init)) {
+            let ops = diff_opcodes(&disassemble(&compiled), &disassemble(&traced));
+            logger.info(format_opcode_diff(&ops, "compiled", "on-chain", 3).trim_end());
+        }
+    }
+
+    // Similar logic results in similar opcode usage, even when metadata or immutables differ
+    if cli_args.verbose {
+        if let (Some(compiled), Some(traced)) = (decode_bytecode(&compile_init), decode_bytecode(&trace_init)) {
+            let distance = histogram_distance(&opcode_histogram(&compiled), &opcode_histogram(&traced));
+            logger.info(&format!("Opcode histogram distance: {:.4}", distance));
+        }
+    }
+
+    // Smart accounts and paymasters have to reject operations that were not validated by the EntryPoint
+    if cli_args.check_erc4337_entrypoint {
+        let report = check_erc4337_compliance(online()?.as_ref(), contract).await?;
+        if !report.is_account() && !report.is_paymaster() {
+            warnings.push("The contract has neither validateUserOp nor validatePaymasterUserOp".to_string());
+        }
+
+        let checks = [
+            ("validateUserOp", report.is_account(), &report.validate_user_op),
+            ("validatePaymasterUserOp", report.is_paymaster(), &report.validate_paymaster_user_op),
+        ];
+        for (function, exists, outcome) in checks {
+            if !exists {
+                continue;
+            }
+
+            logger.info(&format!("{} with an invalid UserOperation {}", function, outcome));
+            if *outcome == CallOutcome::Succeeded {
+                warnings.push(format!("{} accepted an invalid UserOperation that was not sent by the EntryPoint", function));
+            }
+        }
+    }
+
+    // A token that misses functions of its standard breaks wallets and integrations
+    if let Some(standard) = cli_args.check_contract_type {
+        let abi: Abi = match compiler {
+            Compiler::Forge => serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?)?,
+            _ => serde_json::from_str("[]")?,
+        };
+
+        let report = ContractTypeChecker::new(standard).check(online()?.as_ref(), contract, &abi).await?;
+        logger.info(&format!("{} functions present: {}", standard, report.present.len()));
+        for signature in report.missing.iter() {
+            warnings.push(format!("{} function {} is missing", standard, signature));
+        }
+        if !report.optional_present.is_empty() {
+            logger.info(&format!("Optional {} functions: {}", standard, report.optional_present.join(", ")));
+        }
+        for signature in report.overloads.iter() {
+            warnings.push(format!("{} is a non-standard overload of a {} function", signature, standard));
+        }
+        if report.supports_interface == Some(false) {
+            warnings.push(format!("supportsInterface does not return true for the {} interface id", standard));
+        }
+        if report.is_compliant() {
+            logger.info(&format!("The contract implements {}", standard));
+        }
+    }
+
+    // The address of a contract deployed directly by an EOA follows from the sender and its nonce
+    if let (true, Some(tx)) = (cli_args.verify_nonce, transaction.as_ref()) {
+        if tx.to.is_some() {
+            logger.info("The transaction was not sent to create a contract, the address can not be derived from the nonce (e.g. a factory or CREATE2 deployment)");
+        } else {
+            let expected = compute_create_address(tx.from, tx.nonce.as_u64());
+            if expected == contract {
+                logger.info(&format!("The address matches the CREATE address of {:?} at nonce {}", tx.from, tx.nonce));
+            } else {
+                warnings.push(format!(
+                    "The CREATE address of {:?} at nonce {} is {:?}, not {:?}, the contract may not be what you think it is",
+                    tx.from, tx.nonce, expected, contract
+                ));
+            }
+        }
+    }
+
+    // The address of a contract deployed by a CREATE2 factory follows from the factory, the salt and the init code
+    if let Some(salt) = cli_args.verify_create2_salt.as_ref() {
+        let salt = salt.parse::<H256>().map_err(|_| eyre::eyre!("--verify-create2-salt must be 32 bytes of hex"))?;
+        let factory = cli_args.deployer.as_ref()
+            .ok_or_else(|| eyre::eyre!("--verify-create2-salt requires --deployer"))?
+            .parse::<Address>()?;
+
+        let init_code_hash = match cli_args.expected_init_code_hash.as_ref() {
+            Some(hash) => hash.parse::<H256>().map_err(|_| eyre::eyre!("--expected-init-code-hash must be 32 bytes of hex"))?,
+            None if compare_deployed => {
+                return Err(eyre::eyre!("--verify-create2-salt needs the init code, set --expected-init-code-hash when comparing deployed code"));
+            }
+            None => {
+                // The constructor arguments are appended to the init code, they are only known from the traced init code
+                let mut init_code = decode_bytecode(&compile_bytecode).unwrap_or_default();
+                let traced = decode_bytecode(&trace_bytecode).unwrap_or_default();
+                init_code.extend_from_slice(traced.get(init_code.len()..).unwrap_or_default());
+                H256::from(keccak256(&init_code))
+            }
+        };
+
+        let computed = compute_create2_address(factory, salt, init_code_hash);
+        logger.info(&format!("CREATE2 address: {:?} (expected {:?})", computed, contract));
+        if computed != contract {
+            warnings.push(format!(
+                "Critical: the CREATE2 address of {:?} with salt {:?} and init code hash {:?} is {:?}, not {:?}",
+                factory, salt, init_code_hash, computed, contract
+            ));
+        }
+    }
+
+    // A paused contract doesn't work, and one that can't be paused can't be stopped in an emergency
+    let mut pauseability = None;
+    if cli_args.check_pause_mechanism || cli_args.require_pauseable || cli_args.require_not_paused {
+        let abi = match compiler {
+            Compiler::Forge => forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?,
+            _ => "[]".to_string(),
+        };
+
+        let report = check_pauseability(online()?.as_ref(), contract, &abi).await?;
+        if report.is_pauseable() {
+            logger.info(&format!("The contract is pauseable, Paused/Unpaused events: {}", report.has_pause_events));
+        } else if report.has_pause || report.has_unpause {
+            warnings.push("The contract has only one of pause() and unpause()".to_string());
+        } else {
+            logger.info("The contract has no pause mechanism");
+        }
+        match report.paused {
+            Some(true) => warnings.push("The contract is currently paused".to_string()),
+            Some(false) => logger.info("The contract is not paused"),
+            None => {}
+        }
+        pauseability = Some(report);
+    }
+
+    // Whoever holds the admin slot of a transparent proxy can replace its implementation
+    if let Some(expected) = cli_args.check_proxy_admin.as_ref() {
+        let expected = expected.parse::<Address>()?;
+        let admin = read_proxy_admin(online()?.as_ref(), contract).await?;
+        let deployer = transaction.as_ref().map(|tx| tx.from);
+
+        logger.info(&format!("Proxy admin: {:?}", admin));
+        if admin == Address::zero() {
+            warnings.push("The proxy admin slot is empty, the proxy has no admin".to_string());
+        } else if Some(admin) == deployer {
+            warnings.push(format!("The proxy admin is the deployer {:?}, a single key can upgrade the proxy", admin));
+        }
+        if admin != expected {
+            warnings.push(format!("The proxy admin is {:?}, expected {:?}", admin, expected));
+        }
+    }
+
+    // An uninitialized upgradeable contract can be taken over by whoever initializes it first
+    if cli_args.check_initialize_not_called {
+        let receipt = online()?.get_transaction_receipt(tx_hash).await?
+            .ok_or_else(|| eyre::eyre!("Could not find the receipt of transaction {:?}", tx_hash))?;
+
+        let report = check_initialization_state(online()?.as_ref(), contract, &receipt.logs).await?;
+        if !report.initialized_once() {
+            warnings.push(format!(
+                "Expected exactly one Initialized event in the deployment transaction, found {}",
+                report.initialized_events
+            ));
+        }
+        logger.info(&format!("initialize() {}", report.initialize_call));
+        if report.can_be_initialized() {
+            warnings.push("initialize() can still be called, the contract can be initialized by anyone".to_string());
+        }
+    }
+
+    // The same source deployed on another chain should result in the same deployed code
+    if let Some(rpc) = cli_args.cross_verify.as_ref() {
+        let other_client = rpc_client(rpc, rate_limiter.clone())?;
+        let other_chain_id = other_client.get_chainid().await?.as_u64();
+        let other_code = other_client.get_code(contract, None).await?;
+
+        let compiled = remove_metadata(compile_deployed_bytecode.clone().unwrap_or_default());
+        let deployed = remove_metadata(other_code.to_string());
+        if other_code.is_empty() {
+            warnings.push(format!("Cross verification: no code at {:?} on chain {}", contract, other_chain_id));
+        } else if compiled == deployed {
+            logger.info(&format!("Cross verification: the deployed code on chain {} matches", other_chain_id));
+        } else {
+            // Immutables and chain specific constructor arguments end up in the deployed code, so these differences are expected to be explained
+            warnings.push(format!(
+                "Cross verification: the deployed code on chain {} differs from the compiled deployed code (similarity: {:.1}%)",
+                other_chain_id,
+                similarity(&compiled, &deployed) * 100.0
+            ));
+        }
+    }
+
+    // A simulated deployment or `--deployed` already compares the deployed code
+    let deployed_code = match trace_deployed_code.as_ref() {
+        Some(code) => Some(code.to_vec()),
+        None if compare_deployed => decode_bytecode(&trace_bytecode),
+        None => None,
+    };
+    let mut proxy_patterns: Vec<ProxyPattern> = Vec::new();
+    if let Some(deployed_code) = deployed_code.clone() {
+        // Check that it contains no selfdestruct or delegatecall, both allow the code to change what it does
+        let analyzer = BytecodeAnalyzer::new(deployed_code);
+        if analyzer.has_selfdestruct() {
+            warnings.push("The deployed code contains a SELFDESTRUCT".to_string());
+        }
+        if analyzer.has_delegatecall() {
+            warnings.push("The deployed code contains a DELEGATECALL".to_string());
+        }
+
+        // A proxy executes code that was not verified here
+        proxy_patterns = detect_proxy_patterns(analyzer.strip_metadata());
+        for pattern in proxy_patterns.iter() {
+            warnings.push(format!("The deployed code looks like a {}", pattern));
+        }
+    }
+
+    // Policies like "no SELFDESTRUCT, no CREATE" are checked against the code that actually runs
+    if cli_args.opcode_allow_list.is_some() || cli_args.opcode_deny_list.is_some() {
+        let policy = Policy {
+            allowed: cli_args.opcode_allow_list.clone().map(|list| list.0),
+            denied: cli_args.opcode_deny_list.clone().map(|list| list.0).unwrap_or_default(),
+        };
+        let mut code = match deployed_code.as_ref() {
+            Some(code) => code.clone(),
+            None => compile_deployed_bytecode.as_deref().and_then(decode_bytecode).unwrap_or_default(),
+        };
+        // The metadata is data, its bytes would be read as instructions
+        if let Some((range, _)) = ContractMetadata::find(&code) {
+            code.truncate(range.start);
+        }
+
+        let violations = OpcodePolicy::check(&code, &policy);
+        for violation in violations.iter() {
+            warnings.push(format!("Opcode policy violation: {}", violation));
+        }
+        if violations.is_empty() {
+            logger.info("The deployed code follows the opcode policy");
+        }
+    }
+
+    // Who can administer the contract is informational, it is reported but never a warning
+    let mut access_control: Vec<String> = Vec::new();
+    if cli_args.check_access_control {
+        let abi = match compiler {
+            Compiler::Forge => forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?,
+            _ => "[]".to_string(),
+        };
+        let code = match deployed_code.as_ref() {
+            Some(code) => code.clone(),
+            None => compile_deployed_bytecode.as_deref().and_then(decode_bytecode).unwrap_or_default(),
+        };
+
+        access_control = detect_access_control(&abi, &code).iter().map(|pattern| pattern.to_string()).collect();
+        if access_control.is_empty() {
+            logger.info("No access control pattern detected");
+        } else {
+            logger.info(&format!("Access control: {}", access_control.join(", ")));
+        }
+    }
+
+    // Callers of the old implementation break when functions disappear, and its state is corrupted by storage collisions
+    if cli_args.check_upgrade_safety {
+        let old_address = cli_args.old_contract_address.as_deref()
+            .ok_or_else(|| eyre::eyre!("--check-upgrade-safety requires --old-contract-address"))?
+            .parse::<Address>()?;
+        let old_code = online()?.get_code(old_address, None).await?;
+        let new_code = match deployed_code.as_ref() {
+            Some(code) => code.clone(),
+            None => compile_deployed_bytecode.as_deref().and_then(decode_bytecode).unwrap_or_default(),
+        };
+        if storage_layouts.is_none() {
+            logger.info("Storage collisions are only checked together with --compare-storage-layout");
+        }
+        let (old_layout, new_layout) = storage_layouts.unzip();
+
+        let report = UpgradeSafetyReport::compare(
+            &DeployedContract::new(old_code.to_vec(), old_layout),
+            &DeployedContract::new(new_code, new_layout),
+        );
+
+        // Selectors of the new version can be named from its ABI
+        let abi: Abi = match compiler {
+            Compiler::Forge => serde_json::from_str(&forge_inspect(prerequisites, &project_path, forge_profile, &remappings, &contract_name, "abi")?)?,
+            _ => serde_json::from_str("[]")?,
+        };
+        let name = |selector: &String| {
+            abi.functions()
+                .find(|function| format!("0x{}", hex::encode(function.short_signature())) == *selector)
+                .map(|function| format!("{} ({})", canonical_signature(&function.name, &function.inputs), selector))
+                .unwrap_or_else(|| selector.clone())
+        };
+
+        for selector in report.functions_added.iter() {
+            logger.info(&format!("Function added: {}", name(selector)));
+        }
+        for selector in report.functions_changed.iter() {
+            logger.info(&format!("Function changed: {}", name(selector)));
+        }
+        for selector in report.functions_removed.iter() {
+            warnings.push(format!("Function removed by the upgrade: {}", selector));
+        }
+        for topic in report.events_added.iter() {
+            logger.info(&format!("Event added: {}", topic));
+        }
+        for topic in report.events_removed.iter() {
+            logger.info(&format!("Event removed: {}", topic));
+        }
+        for collision in report.storage_collisions.iter() {
+            warnings.push(format!("Storage collision: {}", collision));
+        }
+        if report.is_safe() {
+            logger.info("The upgrade keeps every function and storage variable of the old implementation");
+        }
+    }
+
+    // Contracts that must not hold ETH revert when value is sent to non-payable code
+    let rejects_eth = match deployed_code.as_ref() {
+        Some(code) => Some(has_receive_or_fallback_with_revert(code)),
+        None => compile_deployed_bytecode.as_deref().and_then(decode_bytecode).map(|code| has_receive_or_fallback_with_revert(&code)),
+    };
+    if cli_args.verbose {
+        if let Some(rejects_eth) = rejects_eth {
+            logger.info(&format!("Rejects ETH sent to non-payable code: {}", rejects_eth));
+        }
+    }
+
+    for warning in warnings.iter() {
+        logger.warn(warning);
+    }
+
+    if cli_args.check_no_proxy && !proxy_patterns.is_empty() {
+        return Err(eyre::eyre!("--check-no-proxy is set but a proxy pattern was detected"));
+    }
+    if cli_args.require_reject_eth && rejects_eth != Some(true) {
+        return Err(eyre::eyre!("--require-reject-eth is set but the deployed code has no guard against receiving ETH"));
+    }
+    if let Some(report) = pauseability.as_ref() {
+        if cli_args.require_pauseable && !report.is_pauseable() {
+            return Err(eyre::eyre!("--require-pauseable is set but the contract has no pause() and unpause()"));
+        }
+        if cli_args.require_not_paused && report.paused == Some(true) {
+            return Err(eyre::eyre!("--require-not-paused is set but the contract is paused"));
+        }
+    }
+
+    // Fingerprints can be compared against a registry of known-good builds without verifying again
+    let compiled_fingerprint = BytecodeFingerprint::of(&decode_bytecode(&compile_bytecode).unwrap_or_default());
+    let traced_fingerprint = BytecodeFingerprint::of(&decode_bytecode(&trace_bytecode).unwrap_or_default());
+    if cli_args.verbose {
+        logger.info(&format!("Compiled bytecode fingerprint: {}", compiled_fingerprint));
+        logger.info(&format!("On-chain bytecode fingerprint: {}", traced_fingerprint));
+    }
+
+    // Diffing the bytecodes is not free, so it is skipped when the match already measured it or nothing reports it
+    let reports_similarity = output_format != OutputFormat::Text
+        || cli_args.format_output.is_some()
+        || cli_args.output_report.is_some()
+        || cli_args.output_manifest.is_some();
+    let bytecode_similarity = match match_kind {
+        MatchKind::Exact => 1.0,
+        MatchKind::Fuzzy { similarity, .. } => similarity,
+        _ if reports_similarity => similarity(&compile_init, &trace_init),
+        _ => 0.0,
+    };
+    let result = VerifyResult {
+        contract_name: contract_name.clone(),
+        contract_address: format!("{:?}", contract),
+        transaction: format!("{:?}", tx_hash),
+        git_url: git_url.clone(),
+        commit: commit.clone(),
+        matched,
+        match_kind,
+        similarity: bytecode_similarity,
+        warnings: warnings.clone(),
+        access_control,
+        rejects_eth,
+        code_sizes,
+        compiled_fingerprint,
+        traced_fingerprint,
+    };
+
+    // Everything we know about this verification
+    let report = FullVerifyReport {
+        chain_id,
+        contract_address: format!("{:?}", contract),
+        transaction: format!("{:?}", tx_hash),
+        git_url: git_url.clone(),
+        commit: commit.clone(),
+        matched,
+        similarity: bytecode_similarity,
+        compiled_bytecode: compile_bytecode.clone(),
+        compiled_bytecode_stripped: compile_init.clone(),
+        traced_bytecode: trace_bytecode.clone(),
+        traced_bytecode_stripped: trace_init.clone(),
+        compiled_metadata: decode_metadata(&compile_bytecode),
+        traced_metadata: decode_metadata(&trace_bytecode),
+        warnings: warnings.clone(),
+        timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs(),
+        compiler_version: compiled_solc_version.clone(),
+        cli_args: cli_args_used.clone(),
+    };
+
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
+        OutputFormat::Html => println!("{}", render_html(&report)),
+        OutputFormat::Text | OutputFormat::Csv => {}
+    }
+    if let Some(template) = cli_args.format_output.as_ref() {
+        println!("{}", format_output(template, &result));
+    }
+
+    // Sign the result so it can be published
+    if let Some(signing_key) = signing_key.as_ref() {
+        let proof = VerificationProof {
+            contract_address: format!("{:?}", contract),
+            transaction: format!("{:?}", tx_hash),
+            git_url: git_url.clone(),
+            commit: Some(resolve_head(&prerequisites.git, &project_path)?),
+            compiled_bytecode_hash: sha256_hex(&decode_bytecode(&compile_bytecode).unwrap_or_default()),
+            traced_bytecode_hash: sha256_hex(&decode_bytecode(&trace_bytecode).unwrap_or_default()),
+            matched,
+            timestamp: report.timestamp,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        println!("{}", serde_json::to_string_pretty(&sign_proof(&proof, signing_key))?);
+    }
+
+    if let Some(report_path) = cli_args.output_report.as_ref() {
+        save_report(Path::new(report_path), &report)?;
+    }
+
+    // Only a successful verification is worth repeating
+    if let Some(manifest_path) = cli_args.output_manifest.as_ref() {
+        if matched {
+            save_manifest(Path::new(manifest_path), &cli_args_used, &result)?;
+        } else {
+            logger.warn("Not writing the manifest, the bytecode did not match");
+        }
+    }
+
+
+    // Where to look at the contract, its source code and its transactions next
+    if cli_args.open_explorer || cli_args.print_explorer_url {
+        match explorer_url(chain_id, contract) {
+            Some(url) => {
+                if cli_args.print_explorer_url {
+                    println!("{}", url);
+                }
+                if cli_args.open_explorer {
+                    open::that(&url).map_err(|err| eyre::eyre!("Could not open {} in the browser: {}", url, err))?;
+                }
+            }
+            None => logger.warn(&format!("No block explorer is known for chain id {}", chain_id)),
+        }
+    }
+
+    Ok(result)
+}
+
+fn remove_metadata(
+    bytecode: String, 
+) -> String {
+    // Parse the CBOR metadata, which finds IPFS as well as Swarm (`bzzr0`, `bzzr1`) metadata
+    let hex_start = if bytecode.starts_with("0x") { 2 } else { 0 };
+    if let Some((range, _)) = decode_bytecode(&bytecode).and_then(|bytes| ContractMetadata::find(&bytes)) {
+        return bytecode[..hex_start + range.start * 2].to_string();
+    }
+
+    // Strip all metadata after the metadata delimiter
+    if let Some(index) = bytecode.rfind("a264"){
+        return bytecode.clone().split_at(index).0.to_string();
+    }
+
+    bytecode
+}
+
+/**
+ * Reads the address of a contract from a deployment manifest at `<deployments_dir>/<chain_id>/<contract_name>.json`,
+ * as produced by deployment tooling such as `hardhat-deploy`
+ */
+fn lookup_deployment_address(deployments_dir: &Path, chain_id: u64, contract_name: &str) -> Result<Address> {
+    let manifest_path = deployments_dir
+        .join(chain_id.to_string())
+        .join(format!("{}.json", contract_name));
+
+    let manifest = std::fs::read_to_string(&manifest_path)
+        .map_err(|err| eyre::eyre!("Could not read deployment manifest {}: {}", manifest_path.display(), err))?;
+    let manifest: serde_json::Value = serde_json::from_str(&manifest)?;
+
+    let address = manifest["address"]
+        .as_str()
+        .ok_or_else(|| eyre::eyre!("Deployment manifest {} has no `address` field", manifest_path.display()))?;
+
+    Ok(address.parse::<Address>()?)
+}
+
+/**
+ * An RPC client whose requests go through the rate limiter of the run, every client has to be built with it
+ */
+fn rpc_client(rpc: &str, rate_limiter: Option<Arc<RateLimiter>>) -> Result<Provider<RateLimitedClient<Http>>> {
+    let http: Http = rpc.parse()?;
+
+    Ok(Provider::new(RateLimitedClient::new(http, rate_limiter)))
+}
+
+/**
+ * Prints all traces of the transaction, either as a table or as JSON
+ */
+async fn list_traces(cli_args: &CliArgs, rate_limiter: Option<Arc<RateLimiter>>) -> Result<()> {
+    let tx_hash = cli_args.transaction.clone()
+        .ok_or_else(|| eyre::eyre!("--transaction is required to list traces"))?
+        .parse::<H256>()?;
+    let rpc = cli_args.rpc.clone().ok_or_else(|| eyre::eyre!("--rpc is required to list traces"))?;
+    let client = rpc_client(&rpc, rate_limiter)?;
+
+    let provider_type = cli_args.trace_provider_type.unwrap_or(TraceProvider::Auto);
+    let traces = if cli_args.cache_trace_provider {
+        trace_transaction_cached(&client, tx_hash, provider_type, &rpc).await?
+    } else {
+        trace_transaction(&client, tx_hash, provider_type).await?
+    };
+    let rows: Vec<TraceRow> = traces.iter().map(TraceRow::from_trace).collect();
+
+    if cli_args.print_trace_tree {
+        print!("{}", format_trace_tree(&traces));
+    } else if cli_args.list_traces_json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        print!("{}", format_trace_table(&rows));
+    }
+
+    Ok(())
+}
+
+/**
+ * Prints the compiler version of `--expected-bytecode`, or of the code of every contract address, one `address: version` per line
+ */
+async fn detect_compiler(cli_args: &CliArgs, rate_limiter: Option<Arc<RateLimiter>>) -> Result<()> {
+    let describe = |bytecode: &[u8]| match detect_compiler_version(bytecode) {
+        Ok(Some(version)) => version.to_string(),
+        Ok(None) => "unknown (no compiler version in the metadata)".to_string(),
+        Err(err) => format!("unknown ({})", err),
+    };
+
+    if let Some(bytecode) = cli_args.expected_bytecode.as_ref() {
+        let bytecode = decode_bytecode(bytecode)
+            .ok_or_else(|| eyre::eyre!("--expected-bytecode is not valid hex"))?;
+        println!("{}", describe(&bytecode));
+        return Ok(());
+    }
+
+    let addresses: Vec<&str> = cli_args.contract_address.iter()
+        .chain(cli_args.contract_addresses.iter())
+        .flat_map(|addresses| addresses.split(','))
+        .map(|address| address.trim())
+        .filter(|address| !address.is_empty())
+        .collect();
+    if addresses.is_empty() {
+        return Err(eyre::eyre!("--detect-compiler requires --contract-address, --contract-addresses or --expected-bytecode"));
+    }
+    let client = rpc_client(
+        &cli_args.rpc.clone().ok_or_else(|| eyre::eyre!("--rpc is required to detect the compiler of a contract address"))?,
+        rate_limiter,
+    )?;
+
+    for address in addresses {
+        let code = client.get_code(address.parse::<Address>()?, None).await?;
+        println!("{}: {}", address, describe(&code));
+    }
+
+    Ok(())
+}
+
+/**
+ * Simulates a contract creation with `eth_call` and returns the deployed bytecode.
+ * The sender is given a large balance through a state override so the simulation does not run out of funds.
+ */
+async fn simulate_deployment<P: JsonRpcClient>(provider: &Provider<P>, calldata: &[u8]) -> Result<Bytes> {
+    let sender = Address::zero();
+    let tx: TypedTransaction = TransactionRequest::new()
+        .from(sender)
+        .data(calldata.to_vec())
+        .into();
+
+    let mut state = spoof::state();
+    state.account(sender).balance(U256::MAX);
+
+    let deployed = provider.call_raw(&tx).state(&state).await?;
+    Ok(deployed)
+}
+
+/**
+ * Extracts the solc version from the CBOR metadata in the bytecode, returns `None` if the metadata contains no `solc` field
+ */
+fn extract_solc_version(bytecode: &str) -> Option<String> {
+    decode_metadata(bytecode)?.solc_version()
+}
+
+/**
+ * Decodes the CBOR metadata from hex encoded bytecode
+ */
+fn decode_metadata(bytecode: &str) -> Option<ContractMetadata> {
+    let (_, metadata) = ContractMetadata::find(&decode_bytecode(bytecode)?)?;
 
-    /// Git url of the repository to check against
-    #[interactive_clap(long)]
-    git: String,
+    Some(metadata)
+}
 
-    /// Optional: commit hash of the git repo
-    #[interactive_clap(long)]
-    commit: String,
+/**
+ * Decodes hex encoded bytecode, with or without the `0x` prefix
+ */
+fn decode_bytecode(bytecode: &str) -> Option<Vec<u8>> {
+    hex::decode(bytecode.trim().trim_start_matches("0x")).ok()
+}
 
-    /// Name of the contract (in the git repository) to check against
-    #[interactive_clap(long)]
-    contract_name: String,
+/**
+ * Extracts the bytecode from the output of `forge inspect --json`, any lines before the JSON (e.g. warnings) are ignored.
+ * The bytecode can either be a plain JSON string or an object with an `object` or `bytecode` field.
+ */
+fn parse_forge_inspect_json(output: &str) -> Result<String> {
+    let start = output
+        .find(['{', '"'])
+        .ok_or_else(|| eyre::eyre!("forge inspect did not output any JSON"))?;
+    let value: serde_json::Value = serde_json::from_str(output[start..].trim())?;
 
-    /// HTTP RPC url (has to support `trace` calls)
-    #[interactive_clap(long)]
-    rpc: String,
+    let bytecode = match &value {
+        serde_json::Value::String(bytecode) => Some(bytecode.as_str()),
+        serde_json::Value::Object(_) => value["object"]
+            .as_str()
+            .or_else(|| value["bytecode"].as_str())
+            .or_else(|| value["bytecode"]["object"].as_str()),
+        _ => None,
+    };
+
+    bytecode
+        .map(|bytecode| bytecode.to_string())
+        .ok_or_else(|| eyre::eyre!("Could not find the bytecode in the forge inspect output"))
 }
 
-#[tokio::main]
-async fn main() -> Result<()> { 
-    let mut cli_args = Args::parse();
+/**
+ * Parses the compiler version from the output of `forge inspect <contract> metadata`, stripping the commit suffix
+ */
+fn parse_compiler_version(metadata: &str) -> Option<String> {
+    let metadata: serde_json::Value = serde_json::from_str(metadata).ok()?;
+    let version = metadata["compiler"]["version"].as_str()?;
 
-    let context = ();
-    let args = <Args as interactive_clap::FromCli>::from_cli(Some(cli_args.clone()), context);
-    match args {
-        ResultFromCli::Ok(interactive_args) => {
-            cli_args = interactive_args;
+    Some(version.split('+').next()?.to_string())
+}
 
-            println!(
-                "Your arguments:  {}",
-                shell_words::join(&cli_args.to_cli_args())
-            );
-        },
-        ResultFromCli::Back => {
-            return Ok(());
-        }
-        ResultFromCli::Cancel(_) => {
-            return Ok(());
-        }
-        ResultFromCli::Err(_, err) => {
-            return Err(err);
-        }
+/**
+ * Formats a duration with its largest unit, e.g. `3 days`
+ */
+fn format_duration(duration: chrono::Duration) -> String {
+    if duration.num_days() > 0 {
+        format!("{} days", duration.num_days())
+    } else if duration.num_hours() > 0 {
+        format!("{} hours", duration.num_hours())
+    } else if duration.num_minutes() > 0 {
+        format!("{} minutes", duration.num_minutes())
+    } else {
+        format!("{} seconds", duration.num_seconds())
     }
+}
 
-    // The deployment transaction
-    let tx_hash =
-        cli_args.clone().transaction.unwrap().parse::<H256>()?;
+/**
+ * The full hash of the commit that is checked out in the project
+ */
+fn resolve_head(git: &Path, project_path: &Path) -> Result<String> {
+    let output = run_command(Command::new(git)
+        .args(["rev-parse", "HEAD"])
+        .current_dir(project_path))?;
 
-    // The contract to verify
-    let contract = cli_args.contract_address.unwrap().parse::<Address>()?;
+    if !output.status.success() {
+        return Err(eyre::eyre!("Could not resolve the checked out commit: {}", String::from_utf8_lossy(&output.stderr)));
+    }
 
-    // Build the RPC client
-    let client = Provider::<Http>::try_from(cli_args.rpc.unwrap())?;
-    let client = Arc::new(client);
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
-    // Could be set to Some("") instead of None, if thats the case we force it to be None
-    let mut commit: Option<String> = None;
-    if let Some(hash) = cli_args.commit{
-        if hash != "" {
-            commit = Some(hash);
+/**
+ * A temporary folder that is removed when it goes out of scope, also when we return early with an error
+ */
+struct TempFolder(PathBuf);
+
+impl Drop for TempFolder {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/**
+ * Whether the commit is the tip of one of the refs listed by `git ls-remote`, as `<hash>\t<ref>` lines
+ */
+fn is_ref_tip(refs: &str, commit: &str) -> bool {
+    refs.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|hash| hash.eq_ignore_ascii_case(commit))
+}
+
+/**
+ * Checks whether a commit exists in the remote repository without cloning it, only works with the full hash
+ */
+fn verify_commit_exists(prerequisites: &Prerequisites, git_url: &str, commit: &str) -> Result<bool> {
+    // Servers only hand out single commits by their full hash, and a prefix would match the tip of another ref
+    if commit.len() != 40 {
+        return Err(eyre::eyre!("--anchor-commit needs the full 40 character hash, {} is abbreviated", commit));
+    }
+
+    // The commit might be the tip of one of the refs
+    let output = run_command(Command::new(&prerequisites.git)
+        .args(["ls-remote", git_url])
+        .envs(prerequisites.ssh_env()))?;
+    if !output.status.success() {
+        return Err(eyre::eyre!("Could not list the refs of {}", git_url));
+    }
+
+    if is_ref_tip(&String::from_utf8_lossy(&output.stdout), commit) {
+        return Ok(true);
+    }
+
+    // Otherwise try to fetch only that commit into an empty repository
+    let probe_folder = TempFolder(env::temp_dir().join(format!("bytematch-probe-{}-{}", commit, std::process::id())));
+    std::fs::create_dir_all(&probe_folder.0)?;
+
+    run_checked(Command::new(&prerequisites.git)
+        .args(["init", "--bare", "--quiet"])
+        .current_dir(&probe_folder.0))?;
+    let fetch = run_command(Command::new(&prerequisites.git)
+        .args(["fetch", "--depth", "1", git_url, commit])
+        .envs(prerequisites.ssh_env())
+        .current_dir(&probe_folder.0))?;
+
+    Ok(fetch.status.success())
+}
+
+/**
+ * The bytecode produced by `vyper`
+ */
+struct VyperOutput {
+    /// Init bytecode
+    bytecode: String,
+    /// Deployed bytecode
+    bytecode_runtime: String,
+}
+
+/**
+ * Compiles a Vyper contract, `contract_file` is relative to the project root
+ */
+fn compile_vyper(vyper: &Path, project_path: &Path, contract_file: &str) -> Result<VyperOutput> {
+    let compile = |format: &str| -> Result<String> {
+        let output = run_command(Command::new(vyper)
+            .args(["-f", format, contract_file])
+            .current_dir(project_path))?;
+
+        if !output.status.success() {
+            return Err(eyre::eyre!(
+                "vyper failed to compile {}: {}",
+                contract_file,
+                String::from_utf8_lossy(&output.stderr)
+            ));
         }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    Ok(VyperOutput {
+        bytecode: compile("bytecode")?,
+        bytecode_runtime: compile("bytecode_runtime")?,
+    })
+}
+
+/// Configuration file in the root of Ape projects
+const APE_CONFIG: &str = "ape-config.yaml";
+
+/**
+ * Compiles an Ape project and returns the init bytecode of the contract
+ */
+fn compile_ape(project_path: &Path, contract_name: &str) -> Result<String> {
+    let output = run_command(Command::new("ape")
+        .args(["compile", "--force"])
+        .current_dir(project_path))
+        .map_err(|err| eyre::eyre!("Could not run ape, install it with: pip install eth-ape ({})", err))?;
+
+    if !output.status.success() {
+        return Err(eyre::eyre!("ape failed to compile the project: {}", String::from_utf8_lossy(&output.stderr)));
     }
 
-    let mut spinner = Spinner::new(spinners::Dots, "Fetching traces from the transaction", Color::Blue); 
+    read_ape_artifact(project_path, contract_name, "deploymentBytecode")
+}
+
+/// Config files of a hardhat project
+const HARDHAT_CONFIGS: [&str; 3] = ["hardhat.config.js", "hardhat.config.ts", "hardhat.config.cjs"];
+
+/// The in-process network of hardhat
+const DEFAULT_HARDHAT_NETWORK: &str = "hardhat";
 
-    // Get the trace call to the contract
-    let trace_result = client.trace_transaction(tx_hash).await?;
+/**
+ * The path of the hardhat config relative to the project root, either the given one or the first of `HARDHAT_CONFIGS` that exists
+ */
+fn hardhat_config(project_path: &Path, config_path: Option<&str>) -> Result<PathBuf> {
+    if let Some(path) = config_path {
+        if !project_path.join(path).is_file() {
+            return Err(eyre::eyre!("The hardhat config {} does not exist in the project", path));
+        }
+        return Ok(PathBuf::from(path));
+    }
 
-    // Look through the trace call to find a `CREATE` call
-    let create_trace: Vec<&Trace> = trace_result
+    HARDHAT_CONFIGS
         .iter()
-        .filter(|trace_item| {
-            if trace_item.action_type != ActionType::Create {
-                return false;
-            }
+        .map(PathBuf::from)
+        .find(|config| project_path.join(config).is_file())
+        .ok_or_else(|| eyre::eyre!("Could not find a hardhat config ({}), use --hardhat-config-path", HARDHAT_CONFIGS.join(", ")))
+}
 
-            // For some reason has no result type
-            if trace_item.result.is_none() {
-                return false;
-            }
+/**
+ * Compiles a hardhat project with `npx hardhat compile`, network dependent compiler settings are selected through `HARDHAT_NETWORK`
+ */
+fn compile_hardhat(project_path: &Path, config: &Path, network: &str) -> Result<()> {
+    let npx = which("npx").map_err(|_| eyre::eyre!("npx not found, it is required to compile hardhat projects. Install Node.js from: https://nodejs.org"))?;
 
-            // Check that this is the correct address
-            if let ethers::types::Res::Create(CreateResult {
-                gas_used: _,
-                code: _,
-                address,
-            }) = trace_item.result.clone().unwrap()
-            {
-                return address == contract;
-            }
+    run_checked(Command::new(npx)
+        .args(["hardhat", "compile", "--force", "--config"])
+        .arg(config)
+        .env("HARDHAT_NETWORK", network)
+        .current_dir(project_path))?;
 
-            // It was not the correct address
-            return false;
-        })
+    Ok(())
+}
+
+/**
+ * Reads a bytecode (`bytecode` or `deployedBytecode`) from the artifact at `artifacts/<contract path>/<ContractName>.json`.
+ * Without a contract path the artifacts are searched for the first one with the name of the contract.
+ */
+fn read_hardhat_artifact(project_path: &Path, contract_name: &str, contract_path: Option<&str>, field: &str) -> Result<String> {
+    let artifacts = project_path.join("artifacts");
+    let file_name = format!("{}.json", contract_name);
+
+    let artifact_path = match contract_path {
+        Some(path) => artifacts.join(path).join(&file_name),
+        None => find_file(&artifacts, &file_name)
+            .ok_or_else(|| eyre::eyre!("Could not find the hardhat artifact {} in {}", file_name, artifacts.display()))?,
+    };
+    let artifact = std::fs::read_to_string(&artifact_path)
+        .map_err(|err| eyre::eyre!("Could not read hardhat artifact {}: {}", artifact_path.display(), err))?;
+    let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+
+    artifact[field]
+        .as_str()
+        .map(|bytecode| bytecode.to_string())
+        .ok_or_else(|| eyre::eyre!("Hardhat artifact {} has no {}", artifact_path.display(), field))
+}
+
+/**
+ * Searches the directory and its subdirectories for a file with the name, in a stable order
+ */
+fn find_file(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir).ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
         .collect();
+    entries.sort();
 
-    // The number of items matching should never be more than `1`
-    if create_trace.len() != 1 {
-        // TODO: Error
-        println!(
-            "An unexpected amount of traces were found, {} traces found",
-            create_trace.len()
-        );
+    entries.iter()
+        .find(|path| path.is_file() && path.file_name().is_some_and(|name| name == file_name))
+        .cloned()
+        .or_else(|| entries.iter().filter(|path| path.is_dir()).find_map(|path| find_file(path, file_name)))
+}
+
+/**
+ * Reads a bytecode (`deploymentBytecode` or `runtimeBytecode`) from the artifact at `.build/<ContractName>.json`
+ */
+fn read_ape_artifact(project_path: &Path, contract_name: &str, field: &str) -> Result<String> {
+    let artifact_path = project_path.join(".build").join(format!("{}.json", contract_name));
+    let artifact = std::fs::read_to_string(&artifact_path)
+        .map_err(|err| eyre::eyre!("Could not read ape artifact {}: {}", artifact_path.display(), err))?;
+    let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+
+    artifact[field]["bytecode"]
+        .as_str()
+        .map(|bytecode| bytecode.to_string())
+        .ok_or_else(|| eyre::eyre!("Ape artifact {} has no {}", artifact_path.display(), field))
+}
+
+/// Name of the foundry profile that holds the `--foundry-config-override` settings
+const BYTEMATCH_PROFILE: &str = "bytematch";
+
+/**
+ * Creates a `forge` command that runs inside the project, optionally using the given foundry profile.
+ * With `--docker-build` the project is mounted into a container of the image and `forge` runs there.
+ */
+fn forge_command(prerequisites: &Prerequisites, project_path: &Path, profile: Option<&str>) -> Command {
+    if let (Some(docker), Some(image)) = (prerequisites.docker.as_ref(), prerequisites.docker_image.as_ref()) {
+        let mut command = Command::new(docker);
+        command.args(["run", "--rm", "--entrypoint", "forge", "-v", &format!("{}:/project", project_path.display()), "-w", "/project"]);
+        if let Some(profile) = profile {
+            command.args(["-e", &format!("FOUNDRY_PROFILE={}", profile)]);
+        }
+        command.arg(image);
+        return command;
     }
 
-    spinner.update(spinners::Dots, "Cloning project and installing dependencies", Color::Blue);
+    let mut command = Command::new(&prerequisites.forge);
+    command.current_dir(project_path);
 
-    // Get a temp folder where we can clone the project to
-    let tmp_folder = &mut env::temp_dir();
-    tmp_folder.push(cli_args.contract_name.clone().unwrap());
+    if let Some(profile) = profile {
+        command.env("FOUNDRY_PROFILE", profile);
+    }
 
-    // Clone and configure the project
-    let project_path = configure_project(tmp_folder, String::from(cli_args.git.unwrap()), commit)?;
+    command
+}
 
-    spinner.update(spinners::Dots, "Compiling contract", Color::Blue);
+/**
+ * Writes the exit code, stdout and stderr of a command to a JSON file
+ */
+fn save_command_output(path: &Path, output: &Output) -> Result<()> {
+    let json = serde_json::json!({
+        "exit_code": output.status.code(),
+        "stdout": String::from_utf8_lossy(&output.stdout),
+        "stderr": String::from_utf8_lossy(&output.stderr),
+    });
+    std::fs::write(path, serde_json::to_string_pretty(&json)?)?;
 
-    // Use forge inspect to build the bytecode and get the result
-    let compile_output = Command::new("forge")
-            .args(["inspect", "--force", cli_args.contract_name.unwrap().as_str(), "bytecode"])
-            .current_dir(project_path.clone())
-            .output()?;
+    Ok(())
+}
 
-    let compile_init: String = match str::from_utf8(&compile_output.stdout) {
-        Ok(v) => remove_metadata(v.to_string()),
-        Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
-    };
+/**
+ * The output of `forge --version`, e.g. `forge 0.2.0 (a1b2c3d 2023-08-20T00:16:34.520178600Z)`
+ */
+fn forge_version(prerequisites: &Prerequisites) -> Result<String> {
+    let output = run_checked(Command::new(&prerequisites.forge).arg("--version"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
-    let trace_init: String;
-    if let ethers::types::Action::Create(Create { init, .. }) = create_trace[0].action.clone() {
-        trace_init = remove_metadata(init.to_string());
-    } else {
-        panic!("Could not find trace!");
+/**
+ * Runs `forge inspect` for a field of the contract and returns its output
+ */
+fn forge_inspect(prerequisites: &Prerequisites, project_path: &Path, profile: Option<&str>, remappings: &[String], contract_name: &str, field: &str) -> Result<String> {
+    let mut command = forge_command(prerequisites, project_path, profile);
+    command.args(["inspect", contract_name, field]);
+    for remapping in remappings {
+        command.args(["--remappings", remapping]);
     }
+    let output = run_checked(&mut command)?;
 
-    spinner.stop();
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
-    // Compare the two results
-    if compile_init ==  trace_init{
-        println!("Matching contract deployment!")
-    } else {
-        println!("Did not match")
+/**
+ * The remappings forge detects for the project with `forge remappings`, one `prefix=path` per line
+ */
+fn forge_remappings(prerequisites: &Prerequisites, project_path: &Path, profile: Option<&str>) -> Result<Vec<String>> {
+    let output = run_checked(forge_command(prerequisites, project_path, profile).arg("remappings"))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| line.contains('='))
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/**
+ * Runs the deployment script with `forge script <script> --json` and takes the init code of the first deployment of the contract
+ * from the broadcast at `broadcast/<Script>.s.sol/<chain id>/dry-run/run-latest.json`. The init code includes the constructor
+ * arguments the script passed. `CREATE2` deployments go through the deterministic deployer, which gets the salt before the init code.
+ * With a fork (RPC url and block number) the script runs against the state of that block.
+ */
+fn forge_script_bytecode(prerequisites: &Prerequisites, project_path: &Path, profile: Option<&str>, script: &str, contract_name: &str, fork: Option<(&str, u64)>) -> Result<String> {
+    let mut command = forge_command(prerequisites, project_path, profile);
+    command.args(["script", script, "--json"]);
+    if let Some((rpc, block)) = fork {
+        command.args(["--fork-url", rpc, "--fork-block-number", &block.to_string()]);
+    }
+    run_checked(&mut command)?;
+
+    let script_file = Path::new(script).file_name()
+        .ok_or_else(|| eyre::eyre!("Invalid script path {}", script))?
+        .to_string_lossy()
+        .to_string();
+    let broadcast = project_path.join("broadcast").join(&script_file);
+    let run_path = find_file(&broadcast, "run-latest.json")
+        .ok_or_else(|| eyre::eyre!("forge script did not write a broadcast to {}", broadcast.display()))?;
+    let run: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&run_path)?)?;
+
+    let transaction = run["transactions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|transaction| {
+            transaction["contractName"].as_str() == Some(contract_name)
+                && matches!(transaction["transactionType"].as_str(), Some("CREATE") | Some("CREATE2"))
+        })
+        .ok_or_else(|| eyre::eyre!("{} does not deploy {}", script, contract_name))?;
+
+    // Newer forge versions call the calldata `input`, older ones `data`
+    let input = transaction["transaction"]["input"].as_str()
+        .or_else(|| transaction["transaction"]["data"].as_str())
+        .ok_or_else(|| eyre::eyre!("The deployment of {} in {} has no init code", contract_name, run_path.display()))?
+        .trim_start_matches("0x");
+
+    match transaction["transactionType"].as_str() {
+        Some("CREATE2") => Ok(format!("0x{}", input.get(64..).unwrap_or_default())),
+        _ => Ok(format!("0x{}", input)),
     }
+}
 
+/**
+ * Parses a comma separated list of `key=value` pairs
+ */
+fn parse_config_overrides(input: &str) -> Result<Vec<(String, String)>> {
+    input
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+            None => Err(eyre::eyre!("Invalid config override '{}', expected `key=value`", pair)),
+        })
+        .collect()
+}
 
-    if false {
-        // Check that it contains no selfdestruct
-        // if it does, display a warning
+/**
+ * Appends a `[profile.bytematch]` section with the overrides to the project's foundry.toml, settings that are not overridden fall back to the default profile
+ */
+fn inject_foundry_profile(project_path: &Path, overrides: &[(String, String)]) -> Result<()> {
+    let foundry_toml_path = project_path.join("foundry.toml");
+    let mut foundry_toml = std::fs::read_to_string(&foundry_toml_path).unwrap_or_default();
 
-        // Check that it contains no delegatecall
-        // if it does, display a warning
+    foundry_toml.push_str(&format!("\n[profile.{}]\n", BYTEMATCH_PROFILE));
+    for (key, value) in overrides {
+        // Numbers and booleans are written as is, everything else becomes a string
+        let is_literal = value.parse::<f64>().is_ok() || value == "true" || value == "false";
+        if is_literal {
+            foundry_toml.push_str(&format!("{} = {}\n", key, value));
+        } else {
+            foundry_toml.push_str(&format!("{} = \"{}\"\n", key, value.trim_matches('"')));
+        }
     }
 
+    std::fs::write(&foundry_toml_path, foundry_toml)?;
     Ok(())
 }
 
-fn remove_metadata(
-    bytecode: String, 
-) -> String {
-    // Strip all metadata after the metadata delimiter
-    if let Some(index) = bytecode.rfind("a264"){
-        return bytecode.clone().split_at(index).0.to_string();
+/**
+ * The solc version pinned in `foundry.toml`, the selected profile takes precedence over the default profile
+ */
+fn configured_solc_version(project_path: &Path, profile: Option<&str>) -> Option<String> {
+    let foundry_toml: toml::Table = toml::from_str(&std::fs::read_to_string(project_path.join("foundry.toml")).ok()?).ok()?;
+    let profiles = foundry_toml.get("profile")?.as_table()?;
+
+    let mut names = vec!["default"];
+    if let Some(profile) = profile {
+        names.insert(0, profile);
     }
 
-    return bytecode;
+    names.iter()
+        .filter_map(|name| profiles.get(*name)?.as_table())
+        .find_map(|profile| profile.get("solc_version").or_else(|| profile.get("solc"))?.as_str())
+        .map(|version| version.to_string())
+}
+
+/**
+ * Creates an empty repository with `git_url` as its origin, used to fetch only the commits we need
+ */
+async fn init_repository(prerequisites: &Prerequisites, folder: &Path, git_url: &str) -> Result<()> {
+    std::fs::create_dir_all(folder)?;
+
+    run_command_async(AsyncCommand::new(&prerequisites.git)
+        .args(["init", "--quiet"])
+        .current_dir(folder)).await?;
+    run_command_async(AsyncCommand::new(&prerequisites.git)
+        .args(["remote", "add", "origin", git_url])
+        .current_dir(folder)).await?;
+
+    Ok(())
 }
 
 /**
  * Clones and configures a project ready to be compiled, installs needed dependencies such as npm packages and git submodules
  */
-fn configure_project(
+async fn configure_project(
+    prerequisites: &Prerequisites,
     tmp_folder: &mut PathBuf,
     git_url: String,
-    commit: Option<String>,
+    git_ref: Option<GitRef>,
+    git_cache: Option<&GitWorktreeCache>,
 ) -> Result<PathBuf> {
-    // If a commit hash is set we append it to the path
-    if let Some(hash) = commit.clone() {
-        tmp_folder.push(hash.clone());
+    // If a reference is set we append it to the path
+    if let Some(reference) = git_ref.as_ref() {
+        tmp_folder.push(reference.name());
+    }
+    let destination = tmp_folder.to_str().unwrap();
+    interrupt::register_cleanup(tmp_folder.clone());
+
+    // Start from an empty folder, a previous run may have left its checkout behind
+    if tmp_folder.exists() {
+        std::fs::remove_dir_all(tmp_folder.as_path())?;
     }
 
-    // Clone the repository
-    Command::new("git")
-        .args(["clone", &git_url, tmp_folder.to_str().unwrap()])
-        .output()?;
+    match git_ref {
+        // Check out the reference from the cached clone
+        reference if git_cache.is_some() => {
+            let revision = match reference {
+                Some(GitRef::Tag(tag)) => format!("refs/tags/{}", tag),
+                Some(GitRef::Branch(branch)) => format!("refs/heads/{}", branch),
+                Some(GitRef::Commit(hash)) => hash,
+                None => String::from("HEAD"),
+            };
+            git_cache.unwrap().add_worktree(&git_url, &revision, tmp_folder)?;
+        }
+        // Only fetch the tagged commit
+        Some(GitRef::Tag(tag)) => {
+            init_repository(prerequisites, tmp_folder, &git_url).await?;
+
+            let fetch = run_command_async(AsyncCommand::new(&prerequisites.git)
+                .args(["fetch", "--depth", "1", "origin", &format!("refs/tags/{0}:refs/tags/{0}", tag)])
+                .envs(prerequisites.ssh_env())
+                .current_dir(tmp_folder.clone())).await?;
+            if !fetch.status.success() {
+                return Err(eyre::eyre!("Tag {} does not exist in {}", tag, git_url));
+            }
+
+            run_checked_async(AsyncCommand::new(&prerequisites.git)
+                .args(["checkout", &tag])
+                .current_dir(tmp_folder.clone())).await?;
+        }
+        // Only clone the history of the branch
+        Some(GitRef::Branch(branch)) => {
+            let clone = run_command_async(AsyncCommand::new(&prerequisites.git)
+                .args(["clone", "--single-branch", "--branch", &branch, &git_url, destination])
+                .envs(prerequisites.ssh_env())).await?;
+            if !clone.status.success() {
+                return Err(eyre::eyre!("Branch {} does not exist in {}", branch, git_url));
+            }
+        }
+        // Only fetch the commit and checkout to it
+        Some(GitRef::Commit(hash)) => {
+            init_repository(prerequisites, tmp_folder, &git_url).await?;
+
+            // Servers only hand out single commits by their full hash, an abbreviated one is looked up in the whole history
+            if hash.len() == 40 {
+                let fetch = run_command_async(AsyncCommand::new(&prerequisites.git)
+                    .args(["fetch", "origin", &hash])
+                    .envs(prerequisites.ssh_env())
+                    .current_dir(tmp_folder.clone())).await?;
+                if !fetch.status.success() {
+                    return Err(eyre::eyre!("Commit {} does not exist in {}", hash, git_url));
+                }
+
+                run_checked_async(AsyncCommand::new(&prerequisites.git)
+                    .args(["checkout", "FETCH_HEAD"])
+                    .current_dir(tmp_folder.clone())).await?;
+            } else {
+                run_checked_async(AsyncCommand::new(&prerequisites.git)
+                    .args(["fetch", "origin"])
+                    .envs(prerequisites.ssh_env())
+                    .current_dir(tmp_folder.clone())).await?;
 
-    // Checkout to the commit hash
-    if let Some(hash) = commit { 
-        Command::new("git")
-            .args(["checkout", &hash])
-            .current_dir(tmp_folder.clone())
-            .output()?;
+                let resolved = run_command_async(AsyncCommand::new(&prerequisites.git)
+                    .args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", hash)])
+                    .current_dir(tmp_folder.clone())).await?;
+                if !resolved.status.success() {
+                    return Err(eyre::eyre!("Commit {} does not exist in {} or is ambiguous, pass more characters of the hash", hash, git_url));
+                }
+                let full_hash = String::from_utf8_lossy(&resolved.stdout).trim().to_string();
+
+                run_checked_async(AsyncCommand::new(&prerequisites.git)
+                    .args(["checkout", &full_hash])
+                    .current_dir(tmp_folder.clone())).await?;
+            }
+        }
+        // Clone the repository at its default branch
+        None => {
+            run_checked_async(AsyncCommand::new(&prerequisites.git)
+                .args(["clone", &git_url, destination])
+                .envs(prerequisites.ssh_env())).await?;
+        }
     }
     
     // Check if "package.json" exists
@@ -217,18 +3220,16 @@ fn configure_project(
     packages_path.push("package.json");
     if Path::new(&packages_path).exists() {
         // Install NPM packages
-        if which("yarn").is_ok() {
+        if let Some(yarn) = &prerequisites.yarn {
             // Install using yarn
-            Command::new("yarn")
+            run_checked_async(AsyncCommand::new(yarn)
                 .args(["install"])
-                .current_dir(tmp_folder.clone())
-                .output()?;
-        } else if which("npm").is_ok() {
+                .current_dir(tmp_folder.clone())).await?;
+        } else if let Some(npm) = &prerequisites.npm {
             // Install using NPM
-            Command::new("npm")
+            run_checked_async(AsyncCommand::new(npm)
                 .args(["install"])
-                .current_dir(tmp_folder.clone())
-                .output()?;
+                .current_dir(tmp_folder.clone())).await?;
         } else {
             // TODO: error
         }
@@ -239,17 +3240,163 @@ fn configure_project(
     let mut foundry_toml_path = tmp_folder.clone();
     foundry_toml_path.push("foundry.toml");
     if Path::new(&foundry_toml_path).exists() {
-        // Install git submodules
-        if which("forge").is_ok() {
-            Command::new("forge")
-                .args(["install"])
-                .current_dir(tmp_folder.clone())
-                .output()?;
-        } else {
-            // TODO: error
-        }
+        // Install git submodules, forge clones them with git
+        run_checked_async(AsyncCommand::new(&prerequisites.forge)
+            .args(["install"])
+            .envs(prerequisites.ssh_env())
+            .current_dir(tmp_folder.clone())).await?;
+    }
+
+    // Check if "ape-config.yaml" exists
+    if tmp_folder.join(APE_CONFIG).exists() {
+        // Install the plugins the project needs (e.g. ape-solidity, ape-vyper)
+        run_checked_async(AsyncCommand::new("ape")
+            .args(["plugins", "install", "."])
+            .current_dir(tmp_folder.clone())).await?;
     }
 
     // Return the path
     Ok(tmp_folder.clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_ref_tip, remove_metadata, RefType, TempFolder};
+    use crate::{
+        deployment::DeploymentFinder,
+        mock_provider::{create_trace, MockProvider},
+        report::{match_bytecodes, MatchKind, MatchOptions},
+        trace_provider::TraceProvider,
+    };
+    use ethers::{
+        types::{Address, H256},
+        utils::hex,
+    };
+    use std::sync::Arc;
+
+    /// `{"ipfs": <34 bytes>, "solc": 0.8.19}` followed by its length
+    const METADATA: &str = concat!(
+        "a2646970667358221220",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "64736f6c6343000813",
+        "0033"
+    );
+
+    #[test]
+    fn keeps_bytecode_without_metadata() {
+        let bytecode = "6080604052348015600f57600080fd5b50";
+        assert_eq!(remove_metadata(bytecode.to_string()), bytecode);
+    }
+
+    #[test]
+    fn strips_at_the_metadata_not_at_push_data() {
+        // `PUSH2 0xa264` looks like the delimiter, but the metadata comes later
+        let bytecode = format!("608061a26450{}", METADATA);
+        assert_eq!(remove_metadata(bytecode), "608061a26450");
+    }
+
+    #[test]
+    fn strips_delimiter_at_the_start() {
+        assert_eq!(remove_metadata(METADATA.to_string()), "");
+    }
+
+    #[test]
+    fn strips_swarm_metadata() {
+        let code = "6080604052";
+        let bzzr1 = concat!(
+            "a265627a7a72315820",
+            "2222222222222222222222222222222222222222222222222222222222222222",
+            "64736f6c63430005100032"
+        );
+        assert_eq!(remove_metadata(format!("0x{}{}", code, bzzr1)), format!("0x{}", code));
+    }
+
+    #[test]
+    fn keeps_empty_bytecode() {
+        assert_eq!(remove_metadata(String::new()), "");
+    }
+
+    #[test]
+    fn strips_long_bytecode() {
+        let code = "5b".repeat(50_000);
+        assert_eq!(remove_metadata(format!("{}{}", code, METADATA)), code);
+    }
+
+    #[test]
+    fn compares_full_hashes_of_refs() {
+        let refs = "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2\trefs/heads/main\n";
+        assert!(is_ref_tip(refs, "A1B2C3D4E5F6A1B2C3D4E5F6A1B2C3D4E5F6A1B2"));
+        assert!(!is_ref_tip(refs, "a1b2c3d"));
+        assert!(!is_ref_tip(refs, "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b3"));
+    }
+
+    #[test]
+    fn removes_temp_folders_when_dropped() {
+        let path = std::env::temp_dir().join(format!("bytematch-temp-folder-{}", std::process::id()));
+        std::fs::create_dir_all(path.join("objects")).unwrap();
+
+        drop(TempFolder(path.clone()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn resolves_abbreviated_commits() {
+        assert_eq!(RefType::Auto.resolve("a1b2c3d"), RefType::Commit);
+        assert_eq!(RefType::Auto.resolve(&"a".repeat(40)), RefType::Commit);
+        assert_eq!(RefType::Auto.resolve("v1.2.0"), RefType::Tag);
+        assert_eq!(RefType::Auto.resolve("main"), RefType::Branch);
+        assert_eq!(RefType::Auto.resolve("cafe"), RefType::Branch);
+    }
+
+    /**
+     * Compares the compiled init code with the one traced through a mocked RPC, the way `verify` does
+     */
+    async fn match_deployment(compiled: &str, deployed_init_code: &[u8], options: MatchOptions) -> MatchKind {
+        let (tx_hash, contract) = (H256::repeat_byte(1), Address::repeat_byte(2));
+        let trace = create_trace(Address::repeat_byte(1), contract, deployed_init_code, &[0x00], Vec::new());
+        let finder = DeploymentFinder::new(Arc::new(MockProvider::default().with_traces(tx_hash, vec![trace])))
+            .with_trace_provider(TraceProvider::Parity, None);
+
+        let deployments = finder.find_by_tx(tx_hash).await.unwrap();
+        let trace_bytecode = deployments[0].init_code.to_string();
+        match_bytecodes(&remove_metadata(compiled.to_string()), &remove_metadata(trace_bytecode), &options)
+    }
+
+    #[tokio::test]
+    async fn matches_deployment_with_other_metadata() {
+        let code = "6080604052348015600f57600080fd5b50";
+        let other_metadata = METADATA.replace("11", "22");
+        let deployed = hex::decode(format!("{}{}", code, other_metadata)).unwrap();
+
+        let compiled = format!("0x{}{}", code, METADATA);
+        assert_eq!(match_deployment(&compiled, &deployed, MatchOptions::default()).await, MatchKind::Exact);
+    }
+
+    #[tokio::test]
+    async fn does_not_match_other_deployment() {
+        let deployed = hex::decode(format!("6080604052348015600f57600080fd5b50{}", METADATA)).unwrap();
+
+        let compiled = format!("0x6080604052348015601057600080fd5b50{}", METADATA);
+        assert_eq!(match_deployment(&compiled, &deployed, MatchOptions::default()).await, MatchKind::NoMatch);
+
+        let options = MatchOptions { diff_threshold: Some(1), ..MatchOptions::default() };
+        assert_eq!(
+            match_deployment(&compiled, &deployed, options).await,
+            MatchKind::WithinThreshold { differing_bytes: 1, threshold: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn matches_deployment_with_constructor_arguments_partially() {
+        // Without metadata nothing separates the constructor arguments from the code
+        let code = "6080604052348015600f57600080fd5b50";
+        let arguments = "00".repeat(31) + "2a";
+        let deployed = hex::decode(format!("{}{}", code, arguments)).unwrap();
+
+        let compiled = format!("0x{}", code);
+        assert_eq!(match_deployment(&compiled, &deployed, MatchOptions::default()).await, MatchKind::NoMatch);
+
+        let options = MatchOptions { accept_partial_match: true, ..MatchOptions::default() };
+        assert_eq!(match_deployment(&compiled, &deployed, options).await, MatchKind::Partial);
+    }
+}
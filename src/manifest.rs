@@ -0,0 +1,123 @@
+use crate::{profiles::args_to_table, report::VerifyResult};
+use eyre::Result;
+use std::path::Path;
+
+/// Flags that only make sense for the run that wrote the manifest
+const EXCLUDED_FLAGS: [&str; 4] = ["profile", "save-profile", "output-manifest", "manifest"];
+
+/**
+ * Writes the arguments and the result of a verification as a TOML manifest with a single `[[contracts]]` entry, which
+ * `--manifest` reads. Entries are keyed by the long flag names like profiles, the result is stored in `[contracts.result]`.
+ */
+pub fn save_manifest(path: &Path, cli_args: &[String], result: &VerifyResult) -> Result<()> {
+    let mut entry = args_to_table(cli_args);
+    for flag in EXCLUDED_FLAGS {
+        entry.remove(flag);
+    }
+
+    // Pin the commit that was verified so a re-run checks exactly the same source
+    if let Some(commit) = result.commit.as_ref() {
+        entry.insert("commit".to_string(), toml::Value::String(commit.clone()));
+    }
+
+    let mut result_table = toml::Table::new();
+    result_table.insert("matched".to_string(), toml::Value::Boolean(result.matched));
+    result_table.insert("match-kind".to_string(), toml::Value::String(format!("{}", result.match_kind)));
+    result_table.insert("similarity".to_string(), toml::Value::Float(result.similarity));
+    entry.insert("result".to_string(), toml::Value::Table(result_table));
+
+    let mut manifest = toml::Table::new();
+    manifest.insert("contracts".to_string(), toml::Value::Array(vec![toml::Value::Table(entry)]));
+    std::fs::write(path, toml::to_string_pretty(&manifest)?)?;
+
+    Ok(())
+}
+
+/**
+ * Reads the arguments of every `[[contracts]]` entry of a manifest, keyed by the long flag names. The stored result is
+ * not an argument and is left out.
+ */
+pub fn load_manifest(path: &Path) -> Result<Vec<toml::Table>> {
+    let manifest = std::fs::read_to_string(path)
+        .map_err(|err| eyre::eyre!("Could not read manifest {}: {}", path.display(), err))?;
+    let manifest: toml::Table = toml::from_str(&manifest)?;
+
+    let contracts = manifest
+        .get("contracts")
+        .and_then(|contracts| contracts.as_array())
+        .ok_or_else(|| eyre::eyre!("Manifest {} has no [[contracts]] entries", path.display()))?;
+
+    contracts
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut entry = entry
+                .as_table()
+                .cloned()
+                .ok_or_else(|| eyre::eyre!("Entry {} of manifest {} is not a table", index + 1, path.display()))?;
+            entry.remove("result");
+            Ok(entry)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_manifest, save_manifest};
+    use crate::{
+        fingerprint::BytecodeFingerprint,
+        report::{MatchKind, VerifyResult},
+    };
+
+    fn result() -> VerifyResult {
+        VerifyResult {
+            contract_name: "Counter".to_string(),
+            contract_address: "0x0000000000000000000000000000000000000001".to_string(),
+            transaction: "0x01".to_string(),
+            git_url: "https://github.com/org/counter".to_string(),
+            commit: Some("a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2".to_string()),
+            matched: true,
+            match_kind: MatchKind::Exact,
+            similarity: 1.0,
+            warnings: Vec::new(),
+            access_control: Vec::new(),
+            rejects_eth: None,
+            code_sizes: Vec::new(),
+            compiled_fingerprint: BytecodeFingerprint::of(&[]),
+            traced_fingerprint: BytecodeFingerprint::of(&[]),
+        }
+    }
+
+    #[test]
+    fn reads_the_manifest_it_writes() {
+        let path = std::env::temp_dir().join(format!("bytematch-manifest-{}.toml", std::process::id()));
+        let cli_args: Vec<String> = ["--contract-name", "Counter", "--commit", "main", "--no-spinner", "--output-manifest", "manifest.toml"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+
+        save_manifest(&path, &cli_args, &result()).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let entries = load_manifest(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(written.contains("[[contracts]]") && written.contains("[contracts.result]"));
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["contract-name"].as_str(), Some("Counter"));
+        assert_eq!(entries[0]["no-spinner"].as_bool(), Some(true));
+        // The verified commit replaces the branch, the result and the flags of the writing run are left out
+        assert_eq!(entries[0]["commit"].as_str(), Some("a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2"));
+        assert!(!entries[0].contains_key("result") && !entries[0].contains_key("output-manifest"));
+    }
+
+    #[test]
+    fn rejects_manifests_without_contracts() {
+        let path = std::env::temp_dir().join(format!("bytematch-empty-manifest-{}.toml", std::process::id()));
+        std::fs::write(&path, "rpc = \"http://localhost:8545\"\n").unwrap();
+        let entries = load_manifest(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(entries.unwrap_err().to_string().contains("no [[contracts]] entries"));
+    }
+}
@@ -0,0 +1,258 @@
+use ethers::utils::hex;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{io::Cursor, ops::Range};
+
+/**
+ * The CBOR encoded metadata that solc appends to the bytecode
+ */
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ContractMetadata {
+    /// IPFS hash of the metadata JSON
+    #[serde(default, with = "serde_bytes", skip_serializing_if = "Option::is_none")]
+    pub ipfs: Option<Vec<u8>>,
+
+    /// Swarm hash of the metadata JSON (solc < 0.5.9)
+    #[serde(default, with = "serde_bytes", skip_serializing_if = "Option::is_none")]
+    pub bzzr0: Option<Vec<u8>>,
+
+    /// Swarm hash of the metadata JSON (solc >= 0.5.9)
+    #[serde(default, with = "serde_bytes", skip_serializing_if = "Option::is_none")]
+    pub bzzr1: Option<Vec<u8>>,
+
+    /// Compiler version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solc: Option<SolcVersion>,
+
+    /// Set when experimental features were enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experimental: Option<bool>,
+}
+
+/**
+ * Release builds store the version as 3 bytes, prereleases store the full version string
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SolcVersion {
+    Release(#[serde(with = "serde_bytes")] Vec<u8>),
+    Prerelease(String),
+}
+
+impl std::fmt::Display for SolcVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolcVersion::Release(bytes) => {
+                let parts: Vec<String> = bytes.iter().map(|part| part.to_string()).collect();
+                write!(f, "{}", parts.join("."))
+            }
+            SolcVersion::Prerelease(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+/**
+ * A `major.minor.patch` version, with the prerelease and build of nightly compilers, e.g. `-nightly.2023.4.1+commit.1234abcd`
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub suffix: Option<String>,
+}
+
+impl std::str::FromStr for SemVer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (version, suffix) = match s.find(['-', '+']) {
+            Some(index) => (&s[..index], Some(s[index..].to_string())),
+            None => (s, None),
+        };
+
+        let parts: Vec<u64> = version
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid version '{}'", s))?;
+        match parts.as_slice() {
+            [major, minor, patch] => Ok(SemVer { major: *major, minor: *minor, patch: *patch, suffix }),
+            _ => Err(format!("Invalid version '{}', expected major.minor.patch", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for SemVer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}{}", self.major, self.minor, self.patch, self.suffix.as_deref().unwrap_or_default())
+    }
+}
+
+/**
+ * The content-addressable hash of the metadata JSON, depending on the compiler version either on IPFS or Swarm
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentHash {
+    /// Base58 encoded CIDv0
+    Ipfs(String),
+    /// Hex encoded Swarm hash
+    Bzzr0(String),
+    Bzzr1(String),
+}
+
+impl std::fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentHash::Ipfs(cid) => write!(f, "ipfs://{}", cid),
+            ContentHash::Bzzr0(hash) => write!(f, "bzzr0://{}", hash),
+            ContentHash::Bzzr1(hash) => write!(f, "bzzr1://{}", hash),
+        }
+    }
+}
+
+impl ContractMetadata {
+    /**
+     * Finds the metadata in the bytecode and returns its byte range (including the 2 byte length) together with the decoded metadata.
+     * The metadata is not always at the end of the bytecode, init code has the constructor arguments appended to it.
+     */
+    pub fn find(bytecode: &[u8]) -> Option<(Range<usize>, ContractMetadata)> {
+        for start in (0..bytecode.len()).rev() {
+            // The metadata is always a CBOR map with 1 to 5 entries
+            if !(0xa1..=0xa5).contains(&bytecode[start]) {
+                continue;
+            }
+
+            let mut cursor = Cursor::new(&bytecode[start..]);
+            let metadata: ContractMetadata = match ciborium::de::from_reader(&mut cursor) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            // The metadata is followed by its length as a big-endian u16
+            let end = start + cursor.position() as usize;
+            if let Some(length) = bytecode.get(end..end + 2) {
+                if u16::from_be_bytes([length[0], length[1]]) as usize == end - start {
+                    return Some((start..end + 2, metadata));
+                }
+            }
+        }
+
+        None
+    }
+
+    /**
+     * The hash to fetch the metadata JSON with, IPFS is preferred when the metadata contains more than one
+     */
+    pub fn content_hash(&self) -> Option<ContentHash> {
+        if let Some(ipfs) = self.ipfs.as_ref() {
+            // The IPFS field holds the multihash, which base58 encoded is a CIDv0
+            return Some(ContentHash::Ipfs(bs58::encode(ipfs).into_string()));
+        }
+
+        match (self.bzzr1.as_ref(), self.bzzr0.as_ref()) {
+            (Some(bzzr1), _) => Some(ContentHash::Bzzr1(hex::encode(bzzr1))),
+            (None, Some(bzzr0)) => Some(ContentHash::Bzzr0(hex::encode(bzzr0))),
+            (None, None) => None,
+        }
+    }
+
+    /**
+     * The compiler version as a `major.minor.patch` string
+     */
+    pub fn solc_version(&self) -> Option<String> {
+        self.solc.as_ref().map(|solc| solc.to_string())
+    }
+}
+
+/**
+ * The solc version from the metadata of the bytecode, `None` if there is no metadata or it has no version (solc < 0.5.9).
+ * No compilation is needed, so this is cheap enough to run over many deployed contracts.
+ */
+pub fn detect_compiler_version(bytecode: &[u8]) -> Result<Option<SemVer>> {
+    let version = match ContractMetadata::find(bytecode).and_then(|(_, metadata)| metadata.solc_version()) {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+
+    version.parse::<SemVer>().map(Some).map_err(|err| eyre::eyre!(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_compiler_version, ContentHash, ContractMetadata};
+    use ethers::utils::hex;
+
+    /// `{"ipfs": <34 bytes>, "solc": 0.8.19}` followed by its length
+    const METADATA: &str = concat!(
+        "a2646970667358221220",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "64736f6c6343000813",
+        "0033"
+    );
+
+    fn bytes(hex_string: &str) -> Vec<u8> {
+        hex::decode(hex_string).unwrap()
+    }
+
+    #[test]
+    fn finds_metadata_at_the_end() {
+        let code = "6080604052";
+        let bytecode = bytes(&format!("{}{}", code, METADATA));
+
+        let (range, metadata) = ContractMetadata::find(&bytecode).unwrap();
+        assert_eq!(range, code.len() / 2..bytecode.len());
+        assert_eq!(metadata.solc_version().as_deref(), Some("0.8.19"));
+        assert_eq!(metadata.ipfs.map(|ipfs| ipfs.len()), Some(34));
+    }
+
+    #[test]
+    fn finds_metadata_before_constructor_arguments() {
+        let code = "6080604052";
+        let arguments = "000000000000000000000000000000000000000000000000000000000000002a";
+        let bytecode = bytes(&format!("{}{}{}", code, METADATA, arguments));
+
+        let (range, _) = ContractMetadata::find(&bytecode).unwrap();
+        assert_eq!(range, code.len() / 2..(code.len() + METADATA.len()) / 2);
+    }
+
+    #[test]
+    fn finds_swarm_metadata() {
+        // `{"bzzr0": <32 bytes>}` of solc < 0.5.9 and `{"bzzr1": <32 bytes>, "solc": 0.5.16}`
+        let hash = "2222222222222222222222222222222222222222222222222222222222222222";
+        let bzzr0 = format!("a165627a7a72305820{}0029", hash);
+        let bzzr1 = format!("a265627a7a72315820{}64736f6c63430005100032", hash);
+
+        let (_, metadata) = ContractMetadata::find(&bytes(&format!("6080604052{}", bzzr0))).unwrap();
+        assert_eq!(metadata.content_hash(), Some(ContentHash::Bzzr0(hash.to_string())));
+
+        let (range, metadata) = ContractMetadata::find(&bytes(&format!("6080604052{}", bzzr1))).unwrap();
+        assert_eq!(range.start, 5);
+        assert_eq!(metadata.content_hash(), Some(ContentHash::Bzzr1(hash.to_string())));
+        assert_eq!(metadata.solc_version().as_deref(), Some("0.5.16"));
+    }
+
+    #[test]
+    fn detects_compiler_version() {
+        let version = detect_compiler_version(&bytes(&format!("6080604052{}", METADATA))).unwrap().unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (0, 8, 19));
+        assert_eq!(detect_compiler_version(&bytes("6080604052")).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_metadata_with_wrong_length() {
+        let metadata = METADATA.replace("0033", "0034");
+        assert!(ContractMetadata::find(&bytes(&format!("6080604052{}", metadata))).is_none());
+    }
+
+    #[test]
+    fn ignores_delimiter_in_push_data() {
+        // `PUSH2 0xa264` without any metadata
+        assert!(ContractMetadata::find(&bytes("608061a26450")).is_none());
+    }
+
+    #[test]
+    fn finds_nothing_in_empty_bytecode() {
+        assert!(ContractMetadata::find(&[]).is_none());
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use ethers::{
+    providers::{JsonRpcError, Middleware, MockProvider as MockRpc, MockResponse, Provider, ProviderError},
+    types::{Address, BlockId, Bytes, NameOrAddress, Trace, Transaction, H256},
+};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+/**
+ * A middleware with canned responses for `trace_transaction`, `eth_getCode` and `eth_getTransactionByHash`, so the
+ * verification logic can be tested without a network. Raw requests go to an ethers `MockProvider`, which fails once it
+ * has no responses left. Only the unit tests can use it, bytematch is a binary without a library target that the
+ * integration tests could import it from.
+ */
+#[derive(Debug)]
+pub struct MockProvider {
+    inner: Provider<MockRpc>,
+    /// The traces of a transaction, or the error message the RPC answers with
+    traces: HashMap<H256, Result<Vec<Trace>, String>>,
+    code: HashMap<Address, Bytes>,
+    transactions: HashMap<H256, Transaction>,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        MockProvider {
+            inner: Provider::new(MockRpc::new()),
+            traces: HashMap::new(),
+            code: HashMap::new(),
+            transactions: HashMap::new(),
+        }
+    }
+}
+
+impl MockProvider {
+    pub fn with_traces(mut self, tx_hash: H256, traces: Vec<Trace>) -> Self {
+        self.traces.insert(tx_hash, Ok(traces));
+        self
+    }
+
+    /**
+     * Makes tracing the transaction fail like an unreachable or rate limited RPC would
+     */
+    pub fn with_trace_error(mut self, tx_hash: H256, message: &str) -> Self {
+        self.traces.insert(tx_hash, Err(message.to_string()));
+        self
+    }
+
+    pub fn with_code(mut self, address: Address, code: Bytes) -> Self {
+        self.code.insert(address, code);
+        self
+    }
+
+    pub fn with_transaction(mut self, transaction: Transaction) -> Self {
+        self.transactions.insert(transaction.hash, transaction);
+        self
+    }
+
+    /**
+     * Answers the next raw request, like `debug_traceTransaction`. The ethers mock answers the response pushed last first.
+     */
+    pub fn with_rpc_response<T: Serialize + Send + Sync>(self, response: T) -> Self {
+        self.inner.as_ref().push::<T, T>(response).expect("The response can be serialized");
+        self
+    }
+
+    /**
+     * Answers the next raw request with an error, e.g. a reverting `eth_call` with the revert data as `data`
+     */
+    pub fn with_rpc_error(self, message: &str, data: Option<serde_json::Value>) -> Self {
+        self.inner.as_ref().push_response(MockResponse::Error(JsonRpcError { code: 3, message: message.to_string(), data }));
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for MockProvider {
+    type Error = ProviderError;
+    type Provider = MockRpc;
+    type Inner = Provider<MockRpc>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn trace_transaction(&self, hash: H256) -> Result<Vec<Trace>, Self::Error> {
+        match self.traces.get(&hash) {
+            Some(Ok(traces)) => Ok(traces.clone()),
+            Some(Err(message)) => Err(ProviderError::CustomError(message.clone())),
+            // Nodes answer unknown transactions with no traces
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn get_transaction<T: Send + Sync + Into<H256>>(&self, transaction_hash: T) -> Result<Option<Transaction>, Self::Error> {
+        Ok(self.transactions.get(&transaction_hash.into()).cloned())
+    }
+
+    async fn get_code<T: Into<NameOrAddress> + Send + Sync>(&self, at: T, _block: Option<BlockId>) -> Result<Bytes, Self::Error> {
+        match at.into() {
+            NameOrAddress::Address(address) => Ok(self.code.get(&address).cloned().unwrap_or_default()),
+            NameOrAddress::Name(name) => Err(ProviderError::EnsError(name)),
+        }
+    }
+}
+
+/**
+ * A parity style trace of a successful contract creation
+ */
+pub fn create_trace(deployer: Address, address: Address, init_code: &[u8], code: &[u8], trace_address: Vec<usize>) -> Trace {
+    serde_json::from_value(json!({
+        "type": "create",
+        "action": { "from": deployer, "value": "0x0", "gas": "0x0", "init": Bytes::from(init_code.to_vec()) },
+        "result": { "gasUsed": "0x0", "code": Bytes::from(code.to_vec()), "address": address },
+        "traceAddress": trace_address,
+        "subtraces": 0,
+        "transactionPosition": 0,
+        "transactionHash": H256::zero(),
+        "blockNumber": 1,
+        "blockHash": H256::zero(),
+    }))
+    .expect("The create trace is valid")
+}
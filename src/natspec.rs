@@ -0,0 +1,124 @@
+use crate::signatures::canonical_signature;
+use ethers::abi::Abi;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Top-level keys of the documentation that don't document anything
+const IGNORED_KEYS: [&str; 2] = ["kind", "version"];
+
+/**
+ * The NatSpec documentation of a contract, as printed by `forge inspect <Contract> devdoc` and `userdoc`.
+ * Files passed to `--expected-natspec` use the same format: `{"devdoc": {...}, "userdoc": {...}}`.
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NatSpec {
+    #[serde(default)]
+    pub devdoc: Value,
+    #[serde(default)]
+    pub userdoc: Value,
+}
+
+/**
+ * How the compiled documentation differs from the expected one, entries are paths like `devdoc.methods.transfer(address,uint256)`
+ */
+#[derive(Debug, Clone, Default)]
+pub struct NatSpecDiff {
+    /// Documented in the expected file, but not in the compiled contract
+    pub missing: Vec<String>,
+    /// Documented in the compiled contract, but not in the expected file
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl NatSpecDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+/**
+ * The result of checking the documentation of a contract
+ */
+#[derive(Debug, Clone, Default)]
+pub struct NatSpecReport {
+    /// Signatures of the functions in the ABI that have neither a `@notice` nor any dev documentation
+    pub undocumented: Vec<String>,
+    /// Only set when expected documentation was given
+    pub diff: Option<NatSpecDiff>,
+}
+
+impl NatSpecReport {
+    /**
+     * Every function in the ABI is `public` or `external`, so each of them should be documented.
+     * Public state variables are documented in `devdoc.stateVariables` instead of `methods`.
+     */
+    pub fn check(abi: &Abi, compiled: &NatSpec, expected: Option<&NatSpec>) -> NatSpecReport {
+        let documented = |signature: &str, name: &str| {
+            compiled.devdoc["methods"].get(signature).is_some()
+                || compiled.userdoc["methods"].get(signature).is_some()
+                || compiled.devdoc["stateVariables"].get(name).is_some()
+        };
+
+        let undocumented = abi.functions()
+            .map(|function| (canonical_signature(&function.name, &function.inputs), function.name.clone()))
+            .filter(|(signature, name)| !documented(signature, name))
+            .map(|(signature, _)| signature)
+            .collect();
+
+        NatSpecReport {
+            undocumented,
+            diff: expected.map(|expected| NatSpecReport::compare(compiled, expected)),
+        }
+    }
+
+    /**
+     * Compares the documentation of every method, event, error and state variable, and the contract level tags
+     */
+    pub fn compare(compiled: &NatSpec, expected: &NatSpec) -> NatSpecDiff {
+        let mut diff = NatSpecDiff::default();
+        compare_doc("devdoc", &compiled.devdoc, &expected.devdoc, &mut diff);
+        compare_doc("userdoc", &compiled.userdoc, &expected.userdoc, &mut diff);
+        diff
+    }
+}
+
+/**
+ * Compares one of the docs. Objects that group entries (`methods`, `events`, ...) are compared per entry, everything else as a whole.
+ */
+fn compare_doc(name: &str, compiled: &Value, expected: &Value, diff: &mut NatSpecDiff) {
+    let empty = Map::new();
+    let compiled = compiled.as_object().unwrap_or(&empty);
+    let expected = expected.as_object().unwrap_or(&empty);
+
+    let grouped = |key: &str| ["methods", "events", "errors", "stateVariables"].contains(&key);
+    let entries = |doc: &Map<String, Value>| -> Vec<(String, Value)> {
+        let mut entries = Vec::new();
+        for (key, value) in doc.iter().filter(|(key, _)| !IGNORED_KEYS.contains(&key.as_str())) {
+            match value.as_object() {
+                Some(group) if grouped(key) => {
+                    for (entry, value) in group {
+                        entries.push((format!("{}.{}.{}", name, key, entry), value.clone()));
+                    }
+                }
+                _ => entries.push((format!("{}.{}", name, key), value.clone())),
+            }
+        }
+        entries
+    };
+
+    let compiled_entries = entries(compiled);
+    let expected_entries = entries(expected);
+
+    for (path, expected_value) in expected_entries.iter() {
+        match compiled_entries.iter().find(|(compiled_path, _)| compiled_path == path) {
+            Some((_, compiled_value)) if compiled_value != expected_value => diff.changed.push(path.clone()),
+            Some(_) => {}
+            None => diff.missing.push(path.clone()),
+        }
+    }
+    for (path, _) in compiled_entries.iter() {
+        if !expected_entries.iter().any(|(expected_path, _)| expected_path == path) {
+            diff.added.push(path.clone());
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::opcodes::{disassemble, opcode_name};
+use std::str;
+
+/**
+ * A comma separated list of opcodes, given by name (`SELFDESTRUCT`), hex (`0xff`) or decimal (`255`) value
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpcodeList(pub Vec<u8>);
+
+impl str::FromStr for OpcodeList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|opcode| opcode.trim())
+            .filter(|opcode| !opcode.is_empty())
+            .map(parse_opcode)
+            .collect::<Result<Vec<u8>, String>>()
+            .map(OpcodeList)
+    }
+}
+
+impl std::fmt::Display for OpcodeList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self.0.iter().map(|opcode| format_opcode(*opcode)).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+impl interactive_clap::ToCli for OpcodeList {
+    type CliVariant = OpcodeList;
+}
+
+fn parse_opcode(opcode: &str) -> Result<u8, String> {
+    let parsed = if let Some(hex) = opcode.strip_prefix("0x").or_else(|| opcode.strip_prefix("0X")) {
+        u8::from_str_radix(hex, 16).ok()
+    } else if opcode.chars().all(|c| c.is_ascii_digit()) {
+        opcode.parse::<u8>().ok()
+    } else {
+        (0..=u8::MAX).find(|value| opcode_name(*value).is_some_and(|name| name.eq_ignore_ascii_case(opcode)))
+    };
+
+    parsed.ok_or_else(|| format!("Unknown opcode '{}', expected a name like SELFDESTRUCT or a value like 0xff", opcode))
+}
+
+fn format_opcode(opcode: u8) -> String {
+    match opcode_name(opcode) {
+        Some(name) => name.to_string(),
+        None => format!("0x{:02x}", opcode),
+    }
+}
+
+/**
+ * Which opcodes the deployed code may use: only the allowed ones if there is an allow list, and never the denied ones
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    pub allowed: Option<Vec<u8>>,
+    pub denied: Vec<u8>,
+}
+
+/**
+ * An instruction the policy does not permit
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyViolation {
+    pub offset: usize,
+    pub opcode: u8,
+    /// Whether the opcode is on the deny list, otherwise it is missing from the allow list
+    pub denied: bool,
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = if self.denied { "denied" } else { "not allowed" };
+        write!(f, "{} at offset 0x{:x} is {}", format_opcode(self.opcode), self.offset, reason)
+    }
+}
+
+pub struct OpcodePolicy;
+
+impl OpcodePolicy {
+    /**
+     * Every instruction of the bytecode that violates the policy, the immediate data of `PUSH` instructions is not code and
+     * is skipped. The metadata should be stripped, it would be read as instructions too.
+     */
+    pub fn check(bytecode: &[u8], policy: &Policy) -> Vec<PolicyViolation> {
+        disassemble(bytecode)
+            .into_iter()
+            .filter_map(|instruction| {
+                let denied = policy.denied.contains(&instruction.opcode);
+                let allowed = policy.allowed.as_ref().is_none_or(|allowed| allowed.contains(&instruction.opcode));
+                if denied || !allowed {
+                    Some(PolicyViolation { offset: instruction.offset, opcode: instruction.opcode, denied })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OpcodeList, OpcodePolicy, Policy};
+
+    #[test]
+    fn parses_names_and_values() {
+        let list: OpcodeList = "selfdestruct, 0xf0,241".parse().unwrap();
+        assert_eq!(list.0, vec![0xff, 0xf0, 0xf1]);
+        assert_eq!(list.to_string(), "SELFDESTRUCT,CREATE,CALL");
+
+        assert!("NOTANOPCODE".parse::<OpcodeList>().is_err());
+        assert!("256".parse::<OpcodeList>().is_err());
+    }
+
+    #[test]
+    fn reports_violations_with_offsets() {
+        // PUSH1 0xff CALLER SELFDESTRUCT
+        let bytecode = [0x60, 0xff, 0x33, 0xff];
+
+        let deny = Policy { allowed: None, denied: vec![0xff] };
+        let violations = OpcodePolicy::check(&bytecode, &deny);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].offset, 3);
+        assert_eq!(violations[0].to_string(), "SELFDESTRUCT at offset 0x3 is denied");
+
+        let allow = Policy { allowed: Some(vec![0x60, 0xff]), denied: Vec::new() };
+        let violations = OpcodePolicy::check(&bytecode, &allow);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].opcode, 0x33);
+        assert!(!violations[0].denied);
+    }
+}
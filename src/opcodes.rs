@@ -0,0 +1,407 @@
+use ethers::utils::hex;
+use std::collections::HashMap;
+
+pub const CALLVALUE: u8 = 0x34;
+pub const CODECOPY: u8 = 0x39;
+pub const ISZERO: u8 = 0x15;
+pub const DUP1: u8 = 0x80;
+pub const JUMPI: u8 = 0x57;
+pub const PUSH0: u8 = 0x5f;
+pub const PUSH1: u8 = 0x60;
+pub const PUSH2: u8 = 0x61;
+pub const PUSH32: u8 = 0x7f;
+pub const CALL: u8 = 0xf1;
+pub const CALLCODE: u8 = 0xf2;
+pub const RETURN: u8 = 0xf3;
+pub const DELEGATECALL: u8 = 0xf4;
+pub const STATICCALL: u8 = 0xfa;
+pub const SELFDESTRUCT: u8 = 0xff;
+
+/**
+ * A single EVM instruction, `push_data` holds the immediate value of `PUSH` instructions
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub offset: usize,
+    pub opcode: u8,
+    pub push_data: Vec<u8>,
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match opcode_name(self.opcode) {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "UNKNOWN(0x{:02x})", self.opcode)?,
+        }
+
+        if !self.push_data.is_empty() {
+            write!(f, " 0x{}", hex::encode(&self.push_data))?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * The mnemonic of the opcode, `None` for undefined opcodes
+ */
+pub fn opcode_name(opcode: u8) -> Option<&'static str> {
+    let name = match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60 => "PUSH1",
+        0x61 => "PUSH2",
+        0x62 => "PUSH3",
+        0x63 => "PUSH4",
+        0x64 => "PUSH5",
+        0x65 => "PUSH6",
+        0x66 => "PUSH7",
+        0x67 => "PUSH8",
+        0x68 => "PUSH9",
+        0x69 => "PUSH10",
+        0x6a => "PUSH11",
+        0x6b => "PUSH12",
+        0x6c => "PUSH13",
+        0x6d => "PUSH14",
+        0x6e => "PUSH15",
+        0x6f => "PUSH16",
+        0x70 => "PUSH17",
+        0x71 => "PUSH18",
+        0x72 => "PUSH19",
+        0x73 => "PUSH20",
+        0x74 => "PUSH21",
+        0x75 => "PUSH22",
+        0x76 => "PUSH23",
+        0x77 => "PUSH24",
+        0x78 => "PUSH25",
+        0x79 => "PUSH26",
+        0x7a => "PUSH27",
+        0x7b => "PUSH28",
+        0x7c => "PUSH29",
+        0x7d => "PUSH30",
+        0x7e => "PUSH31",
+        0x7f => "PUSH32",
+        0x80 => "DUP1",
+        0x81 => "DUP2",
+        0x82 => "DUP3",
+        0x83 => "DUP4",
+        0x84 => "DUP5",
+        0x85 => "DUP6",
+        0x86 => "DUP7",
+        0x87 => "DUP8",
+        0x88 => "DUP9",
+        0x89 => "DUP10",
+        0x8a => "DUP11",
+        0x8b => "DUP12",
+        0x8c => "DUP13",
+        0x8d => "DUP14",
+        0x8e => "DUP15",
+        0x8f => "DUP16",
+        0x90 => "SWAP1",
+        0x91 => "SWAP2",
+        0x92 => "SWAP3",
+        0x93 => "SWAP4",
+        0x94 => "SWAP5",
+        0x95 => "SWAP6",
+        0x96 => "SWAP7",
+        0x97 => "SWAP8",
+        0x98 => "SWAP9",
+        0x99 => "SWAP10",
+        0x9a => "SWAP11",
+        0x9b => "SWAP12",
+        0x9c => "SWAP13",
+        0x9d => "SWAP14",
+        0x9e => "SWAP15",
+        0x9f => "SWAP16",
+        0xa0 => "LOG0",
+        0xa1 => "LOG1",
+        0xa2 => "LOG2",
+        0xa3 => "LOG3",
+        0xa4 => "LOG4",
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/**
+ * Splits bytecode into instructions, skipping over the immediate data of `PUSH` instructions.
+ * A `PUSH` at the end of the bytecode gets whatever data is left.
+ */
+pub fn disassemble(bytecode: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+
+    let mut offset = 0;
+    while offset < bytecode.len() {
+        let opcode = bytecode[offset];
+
+        let mut push_size = 0;
+        if (PUSH1..=PUSH32).contains(&opcode) {
+            push_size = (opcode - PUSH1 + 1) as usize;
+        }
+
+        let data_end = (offset + 1 + push_size).min(bytecode.len());
+        instructions.push(Instruction {
+            offset,
+            opcode,
+            push_data: bytecode[offset + 1..data_end].to_vec(),
+        });
+
+        offset += 1 + push_size;
+    }
+
+    instructions
+}
+
+/**
+ * Counts how often each opcode is used
+ */
+pub fn opcode_histogram(bytecode: &[u8]) -> HashMap<u8, usize> {
+    let mut histogram = HashMap::new();
+    for instruction in disassemble(bytecode) {
+        *histogram.entry(instruction.opcode).or_insert(0) += 1;
+    }
+
+    histogram
+}
+
+/**
+ * Distance between two histograms, `0.0` when they are identical and `1.0` when they have no opcodes in common
+ */
+pub fn histogram_distance(a: &HashMap<u8, usize>, b: &HashMap<u8, usize>) -> f64 {
+    let mut difference = 0;
+    let mut total = 0;
+
+    for opcode in a.keys().chain(b.keys().filter(|opcode| !a.contains_key(opcode))) {
+        let count_a = *a.get(opcode).unwrap_or(&0);
+        let count_b = *b.get(opcode).unwrap_or(&0);
+
+        difference += count_a.abs_diff(count_b);
+        total += count_a + count_b;
+    }
+
+    if total == 0 {
+        return 0.0;
+    }
+
+    difference as f64 / total as f64
+}
+
+/**
+ * Checks if the opcode is used by any instruction, values inside `PUSH` data are ignored
+ */
+pub fn contains_opcode(bytecode: &[u8], opcode: u8) -> bool {
+    disassemble(bytecode).iter().any(|instruction| instruction.opcode == opcode)
+}
+
+pub fn contains_selfdestruct(bytecode: &[u8]) -> bool {
+    contains_opcode(bytecode, SELFDESTRUCT)
+}
+
+pub fn contains_delegatecall(bytecode: &[u8]) -> bool {
+    contains_opcode(bytecode, DELEGATECALL)
+}
+
+/**
+ * Returns the constructor part of the init code, which is everything before the embedded deployed code.
+ * The deployed code is located by its first 64 bytes since immutables only get their values during construction,
+ * if it can not be found the whole init code is returned.
+ */
+pub fn constructor_code<'a>(init_code: &'a [u8], deployed_code: &[u8]) -> &'a [u8] {
+    let prefix = &deployed_code[..deployed_code.len().min(64)];
+    if prefix.is_empty() || prefix.len() > init_code.len() {
+        return init_code;
+    }
+
+    // The constructor starts with the same preamble as the deployed code, so the last occurrence is the one we want
+    let start = init_code
+        .windows(prefix.len())
+        .rposition(|window| window == prefix);
+
+    match start {
+        Some(start) if start > 0 => &init_code[..start],
+        _ => init_code,
+    }
+}
+
+/**
+ * Offsets of all `CALL`, `CALLCODE`, `DELEGATECALL` and `STATICCALL` instructions in the constructor part of the init code.
+ * A call during construction can reenter a contract that sees this contract half initialized.
+ */
+pub fn find_calls_in_constructor(init_code: &[u8], deployed_code: &[u8]) -> Vec<usize> {
+    disassemble(constructor_code(init_code, deployed_code))
+        .iter()
+        .filter(|instruction| [CALL, CALLCODE, DELEGATECALL, STATICCALL].contains(&instruction.opcode))
+        .map(|instruction| instruction.offset)
+        .collect()
+}
+
+/**
+ * Whether the code rejects ETH with the guard Solidity emits for non-payable code: `CALLVALUE [DUP1] ISZERO PUSH2 <dest> JUMPI`,
+ * which skips a revert when no value was sent. This is a heuristic, the guard of any non-payable function matches as well.
+ */
+pub fn has_receive_or_fallback_with_revert(bytecode: &[u8]) -> bool {
+    let opcodes: Vec<u8> = disassemble(bytecode).iter().map(|instruction| instruction.opcode).collect();
+
+    opcodes.windows(5).any(|window| window == [CALLVALUE, DUP1, ISZERO, PUSH2, JUMPI])
+        || opcodes.windows(4).any(|window| window == [CALLVALUE, ISZERO, PUSH2, JUMPI])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        contains_delegatecall, contains_selfdestruct, find_calls_in_constructor, has_receive_or_fallback_with_revert, CALL, CALLVALUE,
+        DELEGATECALL, DUP1, ISZERO, JUMPI, PUSH1, PUSH2, PUSH32, SELFDESTRUCT, STATICCALL,
+    };
+
+    /// `CALLER POP STOP`
+    const CODE: [u8; 3] = [0x33, 0x50, 0x00];
+
+    fn with_opcode_at(position: usize, opcode: u8) -> Vec<u8> {
+        let mut bytecode = CODE.to_vec();
+        bytecode.insert(position, opcode);
+        bytecode
+    }
+
+    #[test]
+    fn finds_opcode_at_start_middle_and_end() {
+        for position in [0, 1, CODE.len()] {
+            assert!(contains_selfdestruct(&with_opcode_at(position, SELFDESTRUCT)));
+            assert!(contains_delegatecall(&with_opcode_at(position, DELEGATECALL)));
+        }
+    }
+
+    #[test]
+    fn ignores_opcode_in_push1_data() {
+        assert!(!contains_selfdestruct(&[PUSH1, SELFDESTRUCT, 0x50]));
+        assert!(!contains_delegatecall(&[PUSH1, DELEGATECALL, 0x50]));
+    }
+
+    #[test]
+    fn ignores_opcode_in_push32_data() {
+        let mut bytecode = vec![PUSH32];
+        bytecode.extend([SELFDESTRUCT; 16]);
+        bytecode.extend([DELEGATECALL; 16]);
+        bytecode.push(0x50);
+
+        assert!(!contains_selfdestruct(&bytecode));
+        assert!(!contains_delegatecall(&bytecode));
+    }
+
+    #[test]
+    fn finds_opcode_after_push32_data() {
+        let mut bytecode = vec![PUSH32];
+        bytecode.extend([0x00; 32]);
+        bytecode.push(SELFDESTRUCT);
+
+        assert!(contains_selfdestruct(&bytecode));
+    }
+
+    #[test]
+    fn finds_nothing_in_empty_bytecode() {
+        assert!(!contains_selfdestruct(&[]));
+        assert!(!contains_delegatecall(&[]));
+    }
+
+    #[test]
+    fn finds_calls_only_in_constructor() {
+        let deployed_code = [CODE.as_slice(), [CALL].as_slice()].concat();
+        let init_code = [[STATICCALL, PUSH1, CALL, DELEGATECALL].as_slice(), deployed_code.as_slice()].concat();
+
+        assert_eq!(find_calls_in_constructor(&init_code, &deployed_code), vec![0, 3]);
+    }
+
+    #[test]
+    fn finds_opcode_as_only_instruction() {
+        assert!(contains_selfdestruct(&[SELFDESTRUCT]));
+        assert!(contains_delegatecall(&[DELEGATECALL]));
+    }
+
+    #[test]
+    fn finds_callvalue_guard() {
+        let guarded = [CALLVALUE, DUP1, ISZERO, PUSH2, 0x00, 0x10, JUMPI];
+        assert!(has_receive_or_fallback_with_revert(&guarded));
+        assert!(!has_receive_or_fallback_with_revert(&CODE));
+
+        // The opcodes inside push data are not instructions
+        assert!(!has_receive_or_fallback_with_revert(&[PUSH32, CALLVALUE, ISZERO, PUSH2, 0x00, 0x10, JUMPI]));
+    }
+}
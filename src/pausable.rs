@@ -0,0 +1,132 @@
+use crate::opcodes::disassemble;
+use ethers::{
+    abi::Abi,
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest, H256},
+};
+use eyre::Result;
+
+/// `pause()`, `unpause()` and `paused()` of OpenZeppelin `Pausable`
+const PAUSE_SELECTOR: [u8; 4] = [0x84, 0x56, 0xcb, 0x59];
+const UNPAUSE_SELECTOR: [u8; 4] = [0x3f, 0x4b, 0xa8, 0x3a];
+const PAUSED_SELECTOR: [u8; 4] = [0x5c, 0x97, 0x5a, 0xbb];
+
+/// `Paused(address)` and `Unpaused(address)`
+const PAUSED_TOPIC: [u8; 32] = [
+    0x62, 0xe7, 0x8c, 0xea, 0x01, 0xbe, 0xe3, 0x20, 0xcd, 0x4e, 0x42, 0x02, 0x70, 0xb5, 0xea, 0x74,
+    0x00, 0x0d, 0x11, 0xb0, 0xc9, 0xf7, 0x47, 0x54, 0xeb, 0xdb, 0xfc, 0x54, 0x4b, 0x05, 0xa2, 0x58,
+];
+const UNPAUSED_TOPIC: [u8; 32] = [
+    0x5d, 0xb9, 0xee, 0x0a, 0x49, 0x5b, 0xf2, 0xe6, 0xff, 0x9c, 0x91, 0xa7, 0x83, 0x4c, 0x1b, 0xa4,
+    0xfd, 0xd2, 0x44, 0xa5, 0xe8, 0xaa, 0x4e, 0x53, 0x7b, 0xd3, 0x8a, 0xea, 0xe4, 0xb0, 0x73, 0xaa,
+];
+
+/**
+ * Whether the contract can be paused, and whether it is paused right now
+ */
+#[derive(Debug, Clone)]
+pub struct PauseabilityReport {
+    pub has_pause: bool,
+    pub has_unpause: bool,
+    /// Both the `Paused` and the `Unpaused` event
+    pub has_pause_events: bool,
+    /// The result of `paused()` at the latest block, `None` if it reverted or returned something other than a bool
+    pub paused: Option<bool>,
+}
+
+impl PauseabilityReport {
+    pub fn is_pauseable(&self) -> bool {
+        self.has_pause && self.has_unpause
+    }
+}
+
+/**
+ * Looks for the functions and events of OpenZeppelin `Pausable` in the ABI and in the deployed code, like the access control
+ * patterns, and calls `paused()`
+ */
+pub async fn check_pauseability<M: Middleware>(provider: &M, address: Address, abi_json: &str) -> Result<PauseabilityReport> {
+    let code = provider
+        .get_code(address, None)
+        .await
+        .map_err(|err| eyre::eyre!("Could not fetch the code of {:?}: {}", address, err))?;
+    let pushed: Vec<Vec<u8>> = disassemble(&code).into_iter().map(|instruction| instruction.push_data).collect();
+
+    let abi = serde_json::from_str::<Abi>(abi_json).unwrap_or_default();
+    let has_function = |selector: [u8; 4]| {
+        abi.functions().any(|function| function.short_signature() == selector) || pushed.iter().any(|data| data.as_slice() == selector)
+    };
+    let has_event = |topic: [u8; 32]| {
+        abi.events().any(|event| event.signature() == H256::from(topic)) || pushed.iter().any(|data| data.as_slice() == topic)
+    };
+
+    Ok(PauseabilityReport {
+        has_pause: has_function(PAUSE_SELECTOR),
+        has_unpause: has_function(UNPAUSE_SELECTOR),
+        has_pause_events: has_event(PAUSED_TOPIC) && has_event(UNPAUSED_TOPIC),
+        paused: call_paused(provider, address).await,
+    })
+}
+
+async fn call_paused<M: Middleware>(provider: &M, address: Address) -> Option<bool> {
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(address)
+        .data(PAUSED_SELECTOR.to_vec())
+        .into();
+
+    decode_bool(&provider.call(&tx, None).await.ok()?)
+}
+
+/**
+ * An ABI encoded bool is a 32 byte word that is either `0` or `1`
+ */
+fn decode_bool(result: &[u8]) -> Option<bool> {
+    if result.len() != 32 || result[..31].iter().any(|byte| *byte != 0) || result[31] > 1 {
+        return None;
+    }
+
+    Some(result[31] == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_pauseability, decode_bool, PAUSE_SELECTOR, UNPAUSE_SELECTOR};
+    use crate::mock_provider::MockProvider;
+    use ethers::types::{Address, Bytes};
+
+    fn word(last: u8) -> Vec<u8> {
+        let mut word = vec![0; 32];
+        word[31] = last;
+        word
+    }
+
+    #[test]
+    fn decodes_paused() {
+        assert_eq!(decode_bool(&word(1)), Some(true));
+        assert_eq!(decode_bool(&word(0)), Some(false));
+        assert_eq!(decode_bool(&word(2)), None);
+        assert_eq!(decode_bool(&[1]), None);
+        assert_eq!(decode_bool(&[word(0), word(1)].concat()), None);
+    }
+
+    #[tokio::test]
+    async fn finds_pause_functions_in_the_code() {
+        let address = Address::repeat_byte(1);
+        // `PUSH4 pause() PUSH4 unpause()`
+        let code = [[0x63].as_slice(), &PAUSE_SELECTOR, &[0x63], &UNPAUSE_SELECTOR].concat();
+        let mock = MockProvider::default().with_code(address, Bytes::from(code)).with_rpc_response(Bytes::from(word(1)));
+
+        let report = check_pauseability(&mock, address, "[]").await.unwrap();
+        assert!(report.is_pauseable());
+        assert!(!report.has_pause_events);
+        assert_eq!(report.paused, Some(true));
+    }
+
+    #[tokio::test]
+    async fn reverting_paused_is_unknown() {
+        let mock = MockProvider::default().with_rpc_error("execution reverted", None);
+
+        let report = check_pauseability(&mock, Address::repeat_byte(1), "[]").await.unwrap();
+        assert!(!report.is_pauseable());
+        assert_eq!(report.paused, None);
+    }
+}
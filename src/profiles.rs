@@ -0,0 +1,178 @@
+use eyre::Result;
+use std::{env, path::PathBuf};
+
+/**
+ * Location of the profiles file: `~/.config/bytematch/profiles.toml`.
+ * Every profile is a table keyed by the long flag names, e.g. `rpc = "https://..."` or `no-spinner = true`.
+ */
+fn profiles_path() -> Result<PathBuf> {
+    let home = env::var("HOME").map_err(|_| eyre::eyre!("Could not find the home directory to store profiles in"))?;
+
+    Ok(PathBuf::from(home).join(".config").join("bytematch").join("profiles.toml"))
+}
+
+fn load_profiles() -> Result<toml::Table> {
+    let path = profiles_path()?;
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/**
+ * Finds the value of a flag in the raw arguments, supports both `--flag value` and `--flag=value`
+ */
+fn find_flag_value(args: &[String], flag: &str) -> Option<String> {
+    for (index, arg) in args.iter().enumerate() {
+        if arg == flag {
+            return args.get(index + 1).cloned();
+        }
+        if let Some(value) = arg.strip_prefix(&format!("{}=", flag)) {
+            return Some(value.to_string());
+        }
+    }
+
+    None
+}
+
+/**
+ * Adds the arguments of the profile selected with `--profile` to the raw arguments, arguments that are passed explicitly take precedence
+ */
+pub fn apply_profile(args: Vec<String>) -> Result<Vec<String>> {
+    let name = match find_flag_value(&args, "--profile") {
+        Some(name) => name,
+        None => return Ok(args),
+    };
+
+    let profiles = load_profiles()?;
+    let profile = profiles
+        .get(&name)
+        .and_then(|profile| profile.as_table())
+        .ok_or_else(|| eyre::eyre!("Profile {} does not exist", name))?;
+
+    Ok(merge_profile(args, profile))
+}
+
+/**
+ * Appends the values of the profile whose flags are not in the raw arguments
+ */
+fn merge_profile(args: Vec<String>, profile: &toml::Table) -> Vec<String> {
+    let mut merged = args.clone();
+    for (key, value) in profile {
+        let flag = format!("--{}", key);
+        let is_set = args.iter().any(|arg| arg == &flag || arg.starts_with(&format!("{}=", flag)));
+        if is_set {
+            continue;
+        }
+
+        merged.extend(table_value_to_arg(key, value));
+    }
+
+    merged
+}
+
+/**
+ * The raw argument for a value of a profile or manifest table, `None` for a flag that is turned off
+ */
+pub fn table_value_to_arg(key: &str, value: &toml::Value) -> Option<String> {
+    let flag = format!("--{}", key);
+    match value {
+        toml::Value::Boolean(true) => Some(flag),
+        toml::Value::Boolean(false) => None,
+        toml::Value::String(value) => Some(format!("{}={}", flag, value)),
+        value => Some(format!("{}={}", flag, value)),
+    }
+}
+
+/**
+ * Converts the arguments (as returned by `to_cli_args`) into a table keyed by the long flag names, flags without a value become `true`
+ */
+pub fn args_to_table(cli_args: &[String]) -> toml::Table {
+    let mut table = toml::Table::new();
+
+    let mut index = 0;
+    while index < cli_args.len() {
+        let key = match cli_args[index].strip_prefix("--") {
+            Some(key) => key.to_string(),
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+
+        // Flags are followed by the next flag instead of a value
+        let value = cli_args.get(index + 1).filter(|value| !value.starts_with("--"));
+        match value {
+            Some(value) => {
+                table.insert(key.clone(), toml::Value::String(value.clone()));
+                index += 2;
+            }
+            None => {
+                table.insert(key.clone(), toml::Value::Boolean(true));
+                index += 1;
+            }
+        }
+    }
+
+    table
+}
+
+/**
+ * Saves the arguments (as returned by `to_cli_args`) as a named profile, `--profile` and `--save-profile` themselves are not saved
+ */
+pub fn save_profile(name: &str, cli_args: &[String]) -> Result<()> {
+    let mut profile = args_to_table(cli_args);
+    profile.remove("profile");
+    profile.remove("save-profile");
+
+    let mut profiles = load_profiles()?;
+    profiles.insert(name.to_string(), toml::Value::Table(profile));
+
+    let path = profiles_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, toml::to_string_pretty(&profiles)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{args_to_table, find_flag_value, merge_profile};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_flag_values_in_both_forms() {
+        let raw = args(&["bytematch", "--profile", "mainnet", "--rpc=http://localhost:8545"]);
+
+        assert_eq!(find_flag_value(&raw, "--profile").as_deref(), Some("mainnet"));
+        assert_eq!(find_flag_value(&raw, "--rpc").as_deref(), Some("http://localhost:8545"));
+        assert_eq!(find_flag_value(&raw, "--commit"), None);
+    }
+
+    #[test]
+    fn converts_flags_and_values_to_a_table() {
+        let table = args_to_table(&args(&["--rpc", "http://localhost:8545", "--no-spinner", "--contract-name", "Counter"]));
+
+        assert_eq!(table["rpc"].as_str(), Some("http://localhost:8545"));
+        assert_eq!(table["no-spinner"].as_bool(), Some(true));
+        assert_eq!(table["contract-name"].as_str(), Some("Counter"));
+    }
+
+    #[test]
+    fn explicit_arguments_take_precedence() {
+        let profile: toml::Table = toml::from_str("rpc = \"http://profile:8545\"\ncontract-name = \"Counter\"\nno-spinner = true\nci = false\n").unwrap();
+        let merged = merge_profile(args(&["bytematch", "--rpc=http://explicit:8545"]), &profile);
+
+        assert_eq!(find_flag_value(&merged, "--rpc").as_deref(), Some("http://explicit:8545"));
+        assert_eq!(find_flag_value(&merged, "--contract-name").as_deref(), Some("Counter"));
+        assert!(merged.contains(&"--no-spinner".to_string()));
+        // A flag that is turned off in the profile is left out
+        assert!(!merged.contains(&"--ci".to_string()));
+    }
+}
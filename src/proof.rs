@@ -0,0 +1,72 @@
+use ed25519_dalek::{Signer, SigningKey};
+use ethers::utils::hex;
+use eyre::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/**
+ * The facts of a verification that are signed with `--prove`
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationProof {
+    pub contract_address: String,
+    pub transaction: String,
+    pub git_url: String,
+    /// The commit that was checked out, not the requested reference
+    pub commit: Option<String>,
+    /// sha256 of the compiled bytecode
+    pub compiled_bytecode_hash: String,
+    /// sha256 of the on-chain bytecode
+    pub traced_bytecode_hash: String,
+    pub matched: bool,
+    /// Unix timestamp of when the verification was performed
+    pub timestamp: u64,
+    pub tool_version: String,
+}
+
+/**
+ * A proof together with the ed25519 signature over its JSON encoding and the key to check it with
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedProof {
+    pub proof: VerificationProof,
+    pub public_key: String,
+    pub signature: String,
+}
+
+/**
+ * Hex encoded sha256 hash of the bytes
+ */
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/**
+ * Reads an ed25519 private key stored as 32 hex encoded bytes
+ */
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| eyre::eyre!("Could not read signing key {}: {}", path.display(), err))?;
+    let bytes = hex::decode(content.trim().trim_start_matches("0x"))
+        .map_err(|err| eyre::eyre!("Signing key {} is not hex encoded: {}", path.display(), err))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| eyre::eyre!("Signing key {} must be 32 bytes long", path.display()))?;
+
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/**
+ * Signs the JSON encoding of the proof, the fields are always serialized in the same order
+ */
+pub fn sign_proof(proof: &VerificationProof, key: &SigningKey) -> SignedProof {
+    let message = serde_json::to_vec(proof).expect("A proof only contains strings, numbers and booleans");
+    let signature = key.sign(&message);
+
+    SignedProof {
+        proof: proof.clone(),
+        public_key: hex::encode(key.verifying_key().to_bytes()),
+        signature: hex::encode(signature.to_bytes()),
+    }
+}
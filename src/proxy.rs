@@ -0,0 +1,155 @@
+use crate::opcodes::{contains_delegatecall, disassemble};
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256},
+};
+use eyre::Result;
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+const EIP1967_IMPLEMENTATION_SLOT: [u8; 32] = [
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d,
+    0xca, 0x3e, 0x20, 0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+];
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)`
+const EIP1967_ADMIN_SLOT: [u8; 32] = [
+    0xb5, 0x31, 0x27, 0x68, 0x4a, 0x56, 0x8b, 0x31, 0x73, 0xae, 0x13, 0xb9, 0xf8, 0xa6, 0x01, 0x6e,
+    0x24, 0x3e, 0x63, 0xb6, 0xe8, 0xee, 0x11, 0x78, 0xd6, 0xa7, 0x17, 0x85, 0x0b, 0x5d, 0x61, 0x03,
+];
+
+/// `proxiableUUID()`, implemented by UUPS implementations
+const PROXIABLE_UUID_SELECTOR: [u8; 4] = [0x52, 0xd1, 0x90, 0x2d];
+
+/// `diamondCut((address,uint8,bytes4[])[],address,bytes)` and `facetAddress(bytes4)` of EIP-2535
+const DIAMOND_SELECTORS: [[u8; 4]; 2] = [[0x1f, 0x93, 0x1c, 0x1c], [0xcd, 0xff, 0xac, 0xc6]];
+
+/// The code of an EIP-1167 minimal proxy around the 20 byte implementation address
+const EIP1167_PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+const EIP1167_SUFFIX: [u8; 15] = [0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3];
+
+/**
+ * Proxy and upgradeability patterns that can be recognized in deployed code
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyPattern {
+    /// EIP-1167 minimal proxy (clone)
+    Eip1167,
+    /// EIP-1967 proxy with an admin slot
+    Transparent,
+    /// EIP-1967 proxy without an admin, or an implementation with `proxiableUUID()`
+    Uups,
+    /// EIP-2535 diamond
+    Diamond,
+}
+
+impl std::fmt::Display for ProxyPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProxyPattern::Eip1167 => write!(f, "EIP-1167 minimal proxy"),
+            ProxyPattern::Transparent => write!(f, "transparent proxy"),
+            ProxyPattern::Uups => write!(f, "UUPS proxy"),
+            ProxyPattern::Diamond => write!(f, "diamond proxy"),
+        }
+    }
+}
+
+/**
+ * Finds the proxy patterns used by the deployed code, based on the well-known storage slots and selectors it pushes
+ */
+pub fn detect_proxy_patterns(deployed_code: &[u8]) -> Vec<ProxyPattern> {
+    let mut patterns = Vec::new();
+
+    if deployed_code.len() == EIP1167_PREFIX.len() + 20 + EIP1167_SUFFIX.len()
+        && deployed_code.starts_with(&EIP1167_PREFIX)
+        && deployed_code.ends_with(&EIP1167_SUFFIX)
+    {
+        patterns.push(ProxyPattern::Eip1167);
+        return patterns;
+    }
+
+    let instructions = disassemble(deployed_code);
+    let pushes = |value: &[u8]| instructions.iter().any(|instruction| instruction.push_data == value);
+
+    let delegates = contains_delegatecall(deployed_code);
+    if pushes(&EIP1967_ADMIN_SLOT) && delegates {
+        patterns.push(ProxyPattern::Transparent);
+    } else if (pushes(&EIP1967_IMPLEMENTATION_SLOT) && delegates) || pushes(&PROXIABLE_UUID_SELECTOR) {
+        patterns.push(ProxyPattern::Uups);
+    }
+
+    if DIAMOND_SELECTORS.iter().any(|selector| pushes(selector)) && delegates {
+        patterns.push(ProxyPattern::Diamond);
+    }
+
+    patterns
+}
+
+/**
+ * Reads the admin of a transparent proxy from the EIP-1967 admin slot, the address is in the lower 20 bytes
+ */
+pub async fn read_proxy_admin<M: Middleware>(provider: &M, proxy: Address) -> Result<Address> {
+    let value = provider
+        .get_storage_at(proxy, H256::from(EIP1967_ADMIN_SLOT), None)
+        .await
+        .map_err(|err| eyre::eyre!("Could not read the admin slot of {:?}: {}", proxy, err))?;
+
+    Ok(Address::from_slice(&value.as_bytes()[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        detect_proxy_patterns, ProxyPattern, DIAMOND_SELECTORS, EIP1167_PREFIX, EIP1167_SUFFIX, EIP1967_ADMIN_SLOT,
+        EIP1967_IMPLEMENTATION_SLOT, PROXIABLE_UUID_SELECTOR,
+    };
+
+    const DELEGATECALL: u8 = 0xf4;
+    const PUSH4: u8 = 0x63;
+    const PUSH32: u8 = 0x7f;
+
+    fn push(opcode: u8, value: &[u8]) -> Vec<u8> {
+        let mut code = vec![opcode];
+        code.extend_from_slice(value);
+        code
+    }
+
+    #[test]
+    fn detects_minimal_proxies() {
+        let mut code = EIP1167_PREFIX.to_vec();
+        code.extend([0x11; 20]);
+        code.extend(EIP1167_SUFFIX);
+
+        assert_eq!(detect_proxy_patterns(&code), vec![ProxyPattern::Eip1167]);
+    }
+
+    #[test]
+    fn detects_eip1967_proxies() {
+        let mut transparent = push(PUSH32, &EIP1967_ADMIN_SLOT);
+        transparent.extend(push(PUSH32, &EIP1967_IMPLEMENTATION_SLOT));
+        transparent.push(DELEGATECALL);
+        assert_eq!(detect_proxy_patterns(&transparent), vec![ProxyPattern::Transparent]);
+
+        let mut uups = push(PUSH32, &EIP1967_IMPLEMENTATION_SLOT);
+        uups.push(DELEGATECALL);
+        assert_eq!(detect_proxy_patterns(&uups), vec![ProxyPattern::Uups]);
+
+        // An implementation only has to be upgradeable, it doesn't delegate itself
+        assert_eq!(detect_proxy_patterns(&push(PUSH4, &PROXIABLE_UUID_SELECTOR)), vec![ProxyPattern::Uups]);
+    }
+
+    #[test]
+    fn detects_diamonds() {
+        let mut code = push(PUSH4, &DIAMOND_SELECTORS[0]);
+        code.push(DELEGATECALL);
+
+        assert_eq!(detect_proxy_patterns(&code), vec![ProxyPattern::Diamond]);
+    }
+
+    #[test]
+    fn ignores_slots_without_delegatecall() {
+        assert!(detect_proxy_patterns(&push(PUSH32, &EIP1967_IMPLEMENTATION_SLOT)).is_empty());
+        assert!(detect_proxy_patterns(&push(PUSH4, &DIAMOND_SELECTORS[1])).is_empty());
+        // The slot as data of a `PUSH1`, which is not the whole value
+        assert!(detect_proxy_patterns(&[0x60, 0x36, DELEGATECALL]).is_empty());
+    }
+}
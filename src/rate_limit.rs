@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use ethers::providers::JsonRpcClient;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+
+/**
+ * Token bucket that allows bursts of up to `requests_per_second` requests, permits are refilled one at a time by a background task
+ */
+#[derive(Debug)]
+pub struct RateLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u64) -> Self {
+        let capacity = requests_per_second.max(1) as usize;
+        let semaphore = Arc::new(Semaphore::new(capacity));
+
+        // The refill task stops once the limiter is dropped
+        let bucket: Weak<Semaphore> = Arc::downgrade(&semaphore);
+        tokio::spawn(async move {
+            // The first tick of `interval` is immediate, which would allow one request more than the burst
+            let period = Duration::from_secs(1) / capacity as u32;
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                let semaphore = match bucket.upgrade() {
+                    Some(semaphore) => semaphore,
+                    None => break,
+                };
+                if semaphore.available_permits() < capacity {
+                    semaphore.add_permits(1);
+                }
+            }
+        });
+
+        RateLimiter { semaphore }
+    }
+
+    /**
+     * Waits until a request may be sent, the permit is used up and only comes back through the refill task
+     */
+    pub async fn acquire(&self) {
+        if let Ok(permit) = self.semaphore.acquire().await {
+            permit.forget();
+        }
+    }
+}
+
+/**
+ * Wraps a JSON-RPC transport so every request, from any task, goes through the same rate limiter
+ */
+#[derive(Debug)]
+pub struct RateLimitedClient<C> {
+    inner: C,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<C> RateLimitedClient<C> {
+    pub fn new(inner: C, limiter: Option<Arc<RateLimiter>>) -> Self {
+        RateLimitedClient { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<C: JsonRpcClient> JsonRpcClient for RateLimitedClient<C> {
+    type Error = C::Error;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        if let Some(limiter) = self.limiter.as_ref() {
+            limiter.acquire().await;
+        }
+
+        self.inner.request(method, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimiter;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn allows_a_burst_then_waits_for_the_refill() {
+        let limiter = RateLimiter::new(20);
+
+        let start = Instant::now();
+        for _ in 0..20 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+
+        // The bucket is empty, a permit is added every 50ms
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn does_not_refill_beyond_the_burst() {
+        let limiter = RateLimiter::new(20);
+        limiter.acquire().await;
+
+        // Several refills pass while the limiter is idle, but only one permit was used
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(limiter.semaphore.available_permits(), 20);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{code_size::CodeSize, diff::BytecodeDiffStats, fingerprint::BytecodeFingerprint, metadata::ContractMetadata};
+use ethers::utils::hex;
+use eyre::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/**
+ * How the compiled bytecode matched the on-chain bytecode
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    /// The compiled bytecode is a prefix of the on-chain bytecode
+    Partial,
+    /// The bytecodes differ, but their similarity is above the `--fuzzy-match` threshold
+    Fuzzy { similarity: f64, threshold: f64 },
+    /// At most `--diff-threshold` bytes differ
+    WithinThreshold { differing_bytes: usize, threshold: usize },
+    NoMatch,
+}
+
+impl std::fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchKind::Exact => write!(f, "Matching contract deployment!"),
+            MatchKind::Partial => write!(f, "Partial match — additional bytes follow compiled bytecode"),
+            MatchKind::Fuzzy { similarity, threshold } => write!(
+                f,
+                "Fuzzy match (similarity: {:.1}% ≥ threshold {:.1}%)",
+                similarity * 100.0,
+                threshold * 100.0
+            ),
+            MatchKind::WithinThreshold { differing_bytes, threshold } => write!(
+                f,
+                "Match within threshold ({} differing bytes ≤ threshold {})",
+                differing_bytes,
+                threshold
+            ),
+            MatchKind::NoMatch => write!(f, "Did not match"),
+        }
+    }
+}
+
+/**
+ * The outcome of a verification, printed with `--output json`
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyResult {
+    pub contract_name: String,
+    pub contract_address: String,
+    pub transaction: String,
+    pub git_url: String,
+    pub commit: Option<String>,
+    pub matched: bool,
+    pub match_kind: MatchKind,
+    pub similarity: f64,
+    pub warnings: Vec<String>,
+    /// Access control patterns found with `--check-access-control`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub access_control: Vec<String>,
+    /// Whether the deployed code guards against receiving ETH, `None` if there was no deployed code to inspect
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejects_eth: Option<bool>,
+    /// Sizes of the compiled and on-chain bytecodes, with the EIP-3860 or EIP-170 limit
+    pub code_sizes: Vec<CodeSize>,
+    pub compiled_fingerprint: BytecodeFingerprint,
+    pub traced_fingerprint: BytecodeFingerprint,
+}
+
+/**
+ * Everything that is known about a single verification, written to disk with `--output-report`
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct FullVerifyReport {
+    pub chain_id: u64,
+    pub contract_address: String,
+    pub transaction: String,
+    pub git_url: String,
+    pub commit: Option<String>,
+    pub matched: bool,
+    pub similarity: f64,
+    pub compiled_bytecode: String,
+    pub compiled_bytecode_stripped: String,
+    pub traced_bytecode: String,
+    pub traced_bytecode_stripped: String,
+    pub compiled_metadata: Option<ContractMetadata>,
+    pub traced_metadata: Option<ContractMetadata>,
+    pub warnings: Vec<String>,
+    /// Unix timestamp of when the verification was performed
+    pub timestamp: u64,
+    pub compiler_version: Option<String>,
+    pub cli_args: Vec<String>,
+}
+
+/**
+ * Writes the report as pretty printed JSON
+ */
+pub fn save_report(path: &Path, result: &FullVerifyReport) -> Result<()> {
+    let json = serde_json::to_string_pretty(result)?;
+    std::fs::write(path, json)?;
+
+    Ok(())
+}
+
+/**
+ * Formats the results as CSV with a row per contract, the warnings are joined into a single column
+ */
+pub fn format_csv(results: &[VerifyResult]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["contract_name", "contract_address", "transaction", "git", "commit", "match", "similarity", "warnings"])?;
+
+    for result in results {
+        writer.write_record([
+            result.contract_name.clone(),
+            result.contract_address.clone(),
+            result.transaction.clone(),
+            result.git_url.clone(),
+            result.commit.clone().unwrap_or_default(),
+            result.matched.to_string(),
+            format!("{:.4}", result.similarity),
+            result.warnings.join("; "),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/**
+ * Fills in the placeholders of a `--format-output` template: `{match}`, `{match_kind}`, `{contract}`, `{name}`, `{tx}`,
+ * `{git}`, `{commit}`, `{similarity}` and `{warnings}` (joined with `; `). Unknown placeholders are left as they are.
+ */
+pub fn format_output(template: &str, result: &VerifyResult) -> String {
+    let placeholders = [
+        ("{match}", result.matched.to_string()),
+        ("{match_kind}", result.match_kind.to_string()),
+        ("{contract}", result.contract_address.clone()),
+        ("{name}", result.contract_name.clone()),
+        ("{tx}", result.transaction.clone()),
+        ("{git}", result.git_url.clone()),
+        ("{commit}", result.commit.clone().unwrap_or_default()),
+        ("{similarity}", format!("{:.4}", result.similarity)),
+        ("{warnings}", result.warnings.join("; ")),
+    ];
+
+    placeholders
+        .iter()
+        .fold(template.to_string(), |output, (placeholder, value)| output.replace(placeholder, value))
+}
+
+/**
+ * Which differences between the bytecodes are still accepted as a match, besides an exact match
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchOptions {
+    /// `--accept-partial-match`
+    pub accept_partial_match: bool,
+    /// `--fuzzy-match`
+    pub fuzzy_match: Option<f64>,
+    /// `--diff-threshold`
+    pub diff_threshold: Option<usize>,
+}
+
+/**
+ * Compares the compiled bytecode with the on-chain bytecode, both hex encoded and without their metadata
+ */
+pub fn match_bytecodes(compiled: &str, traced: &str, options: &MatchOptions) -> MatchKind {
+    if compiled == traced {
+        return MatchKind::Exact;
+    }
+    if options.accept_partial_match && !compiled.is_empty() && traced.starts_with(compiled) {
+        return MatchKind::Partial;
+    }
+    if options.fuzzy_match.is_none() && options.diff_threshold.is_none() {
+        return MatchKind::NoMatch;
+    }
+
+    // Both tolerances are measured on the same edit script
+    let stats = diff_stats(compiled, traced);
+    if let Some(threshold) = options.fuzzy_match {
+        let similarity = stats.similarity();
+        if similarity >= threshold {
+            return MatchKind::Fuzzy { similarity, threshold };
+        }
+    }
+    if let Some(threshold) = options.diff_threshold {
+        if stats.differing_bytes <= threshold {
+            return MatchKind::WithinThreshold { differing_bytes: stats.differing_bytes, threshold };
+        }
+    }
+
+    MatchKind::NoMatch
+}
+
+/**
+ * How many bytes the shortest edit script between the bytecodes replaces, inserts or deletes
+ */
+pub fn differing_bytes(a: &str, b: &str) -> usize {
+    diff_stats(a, b).differing_bytes
+}
+
+/**
+ * The share of bytes that the shortest edit script between the bytecodes keeps, `1.0` means the bytecodes are identical.
+ * An inserted byte only lowers it by one byte, instead of shifting everything after it out of place.
+ */
+pub fn similarity(a: &str, b: &str) -> f64 {
+    diff_stats(a, b).similarity()
+}
+
+fn diff_stats(a: &str, b: &str) -> BytecodeDiffStats {
+    BytecodeDiffStats::compute(&bytes_of(a), &bytes_of(b))
+}
+
+/**
+ * The decoded bytecode, text that is not valid hex is compared as it is
+ */
+fn bytes_of(bytecode: &str) -> Vec<u8> {
+    hex::decode(bytecode.trim_start_matches("0x")).unwrap_or_else(|_| bytecode.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{differing_bytes, similarity};
+
+    #[test]
+    fn compares_shifted_bytecode() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("0x60806040", "0x60806040"), 1.0);
+        assert_eq!(similarity("0x60806040", ""), 0.0);
+
+        // One inserted byte in ten
+        assert!((similarity("0x608060405234801561", "0x60806040005234801561") - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn counts_inserted_bytes_once() {
+        assert_eq!(differing_bytes("0x608060405234801561", "0x608060405234801561"), 0);
+        assert_eq!(differing_bytes("0x608060405234801561", "0x60806040005234801561"), 1);
+        assert_eq!(differing_bytes("0x608060405234801561", "0x608160405234801561"), 1);
+    }
+}
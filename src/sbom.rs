@@ -0,0 +1,198 @@
+use crate::interrupt::run_command;
+use eyre::Result;
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/**
+ * The parts of the solc metadata (`forge inspect <Contract> metadata`) that describe how the contract was built
+ */
+#[derive(Debug, Clone)]
+pub struct ForgeArtifact {
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub optimizer_enabled: bool,
+    pub optimizer_runs: u64,
+    pub evm_version: Option<String>,
+    /// Source files with their keccak256 hash
+    pub sources: Vec<(String, Option<String>)>,
+}
+
+impl ForgeArtifact {
+    pub fn from_metadata(contract_name: &str, metadata: &str) -> Result<ForgeArtifact> {
+        let metadata: serde_json::Value = serde_json::from_str(metadata)?;
+        let optimizer = &metadata["settings"]["optimizer"];
+
+        Ok(ForgeArtifact {
+            contract_name: contract_name.to_string(),
+            compiler_version: metadata["compiler"]["version"]
+                .as_str()
+                .ok_or_else(|| eyre::eyre!("The metadata of {} has no compiler version", contract_name))?
+                .to_string(),
+            optimizer_enabled: optimizer["enabled"].as_bool().unwrap_or(false),
+            optimizer_runs: optimizer["runs"].as_u64().unwrap_or(0),
+            evm_version: metadata["settings"]["evmVersion"].as_str().map(String::from),
+            sources: metadata["sources"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(path, source)| (path.clone(), source["keccak256"].as_str().map(String::from)))
+                .collect(),
+        })
+    }
+}
+
+/**
+ * A CycloneDX 1.5 JSON document, only the fields that describe a compiled contract are used
+ */
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycloneDxBom {
+    pub bom_format: String,
+    pub spec_version: String,
+    pub version: u32,
+    pub metadata: BomMetadata,
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BomMetadata {
+    /// The compiled contract itself
+    pub component: Component,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Component {
+    /// `application`, `library` or `file`
+    #[serde(rename = "type")]
+    pub component_type: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub external_references: Vec<ExternalReference>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalReference {
+    #[serde(rename = "type")]
+    pub reference_type: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+}
+
+impl Component {
+    fn new(component_type: &str, name: &str, version: Option<String>) -> Component {
+        Component {
+            component_type: component_type.to_string(),
+            name: name.to_string(),
+            version,
+            external_references: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    fn with_property(mut self, name: &str, value: impl ToString) -> Component {
+        self.properties.push(Property { name: name.to_string(), value: value.to_string() });
+        self
+    }
+}
+
+/**
+ * Lists the compiler, the source files and the git submodules in `lib/` (with their url and pinned commit) of the compiled contract
+ */
+pub fn generate_sbom(git: &Path, project_path: &Path, compiled: &ForgeArtifact) -> Result<CycloneDxBom> {
+    let mut contract = Component::new("application", &compiled.contract_name, None)
+        .with_property("solc:optimizer.enabled", compiled.optimizer_enabled)
+        .with_property("solc:optimizer.runs", compiled.optimizer_runs);
+    if let Some(evm_version) = compiled.evm_version.as_ref() {
+        contract = contract.with_property("solc:evmVersion", evm_version);
+    }
+
+    let mut components = vec![Component::new("application", "solc", Some(compiled.compiler_version.clone()))];
+    for (path, keccak256) in compiled.sources.iter() {
+        let mut source = Component::new("file", path, None);
+        if let Some(hash) = keccak256 {
+            source = source.with_property("keccak256", hash);
+        }
+        components.push(source);
+    }
+
+    let submodule_urls = read_submodule_urls(project_path);
+    let mut libraries: Vec<PathBuf> = match std::fs::read_dir(project_path.join("lib")) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect(),
+        Err(_) => Vec::new(),
+    };
+    libraries.sort();
+    for library in libraries {
+        let name = library.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let mut component = Component::new("library", &name, head_commit(git, &library));
+
+        let relative = format!("lib/{}", name);
+        if let Some((_, url)) = submodule_urls.iter().find(|(path, _)| *path == relative) {
+            component.external_references.push(ExternalReference { reference_type: "vcs".to_string(), url: url.clone() });
+        }
+        components.push(component);
+    }
+
+    Ok(CycloneDxBom {
+        bom_format: "CycloneDX".to_string(),
+        spec_version: "1.5".to_string(),
+        version: 1,
+        metadata: BomMetadata { component: contract },
+        components,
+    })
+}
+
+pub fn save_sbom(path: &Path, bom: &CycloneDxBom) -> Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(bom)?)?;
+
+    Ok(())
+}
+
+/**
+ * The `path` and `url` of every `[submodule]` in `.gitmodules`
+ */
+fn read_submodule_urls(project_path: &Path) -> Vec<(String, String)> {
+    let gitmodules = std::fs::read_to_string(project_path.join(".gitmodules")).unwrap_or_default();
+
+    let mut submodules = Vec::new();
+    let (mut path, mut url): (Option<String>, Option<String>) = (None, None);
+    for line in gitmodules.lines().map(|line| line.trim()) {
+        if line.starts_with("[submodule") {
+            path = None;
+            url = None;
+        } else if let Some((key, value)) = line.split_once('=') {
+            match key.trim() {
+                "path" => path = Some(value.trim().to_string()),
+                "url" => url = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+
+        if path.is_some() && url.is_some() {
+            submodules.push((path.take().unwrap_or_default(), url.take().unwrap_or_default()));
+        }
+    }
+
+    submodules
+}
+
+fn head_commit(git: &Path, path: &Path) -> Option<String> {
+    let output = run_command(Command::new(git).args(["rev-parse", "HEAD"]).current_dir(path)).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
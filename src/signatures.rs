@@ -0,0 +1,68 @@
+use crate::events::event_signature;
+use ethers::{abi::{Abi, Param}, utils::hex};
+use eyre::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/**
+ * A function of the ABI with its 4 byte selector
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionSignature {
+    pub signature: String,
+    pub selector: String,
+}
+
+/**
+ * An event of the ABI with its `topics[0]`, anonymous events have no topic
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSignature {
+    pub signature: String,
+    pub topic0: Option<String>,
+}
+
+/**
+ * All signatures of a contract, as written by `--export-function-sigs`
+ */
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SignatureExport {
+    pub functions: Vec<FunctionSignature>,
+    pub events: Vec<EventSignature>,
+}
+
+impl SignatureExport {
+    pub fn from_abi(abi: &Abi) -> SignatureExport {
+        let functions = abi.functions()
+            .map(|function| FunctionSignature {
+                signature: canonical_signature(&function.name, &function.inputs),
+                selector: format!("0x{}", hex::encode(function.short_signature())),
+            })
+            .collect();
+
+        let events = abi.events()
+            .map(|event| EventSignature {
+                signature: event_signature(event),
+                topic0: (!event.anonymous).then(|| format!("{:?}", event.signature())),
+            })
+            .collect();
+
+        SignatureExport { functions, events }
+    }
+
+    /**
+     * Writes the signatures as pretty printed JSON
+     */
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/**
+ * `name(type1,type2)`, the text that is hashed to get the selector
+ */
+pub fn canonical_signature(name: &str, inputs: &[Param]) -> String {
+    let types: Vec<String> = inputs.iter().map(|input| input.kind.to_string()).collect();
+    format!("{}({})", name, types.join(","))
+}
@@ -0,0 +1,47 @@
+use crate::{interrupt::run_checked, metadata::SemVer};
+use eyre::Result;
+use std::process::Command;
+use which::which;
+
+/// The release list svm uses, per platform
+const SOLC_BINARIES_URL: &str = "https://binaries.soliditylang.org";
+
+fn platform() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macosx-amd64"
+    } else if cfg!(target_os = "windows") {
+        "windows-amd64"
+    } else {
+        "linux-amd64"
+    }
+}
+
+/**
+ * All solc releases for this platform, newest first, from the `list.json` of the official binaries
+ */
+pub async fn list_solc_versions() -> Result<Vec<String>> {
+    let url = format!("{}/{}/list.json", SOLC_BINARIES_URL, platform());
+    let list: serde_json::Value = reqwest::get(&url).await?.error_for_status()?.json().await?;
+
+    let mut versions: Vec<SemVer> = list["releases"]
+        .as_object()
+        .ok_or_else(|| eyre::eyre!("{} has no releases", url))?
+        .keys()
+        .filter_map(|version| version.parse().ok())
+        .collect();
+    versions.sort();
+
+    Ok(versions.iter().rev().map(|version| version.to_string()).collect())
+}
+
+/**
+ * Installs the solc version with `svm install`, forge picks versions installed by svm up
+ */
+pub fn install_solc_version(version: &str) -> Result<()> {
+    version.parse::<SemVer>().map_err(|err| eyre::eyre!(err))?;
+    let svm = which("svm").map_err(|_| eyre::eyre!("svm not found. Install it with: cargo install svm-rs"))?;
+
+    run_checked(Command::new(svm).args(["install", version]))?;
+
+    Ok(())
+}
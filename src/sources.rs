@@ -0,0 +1,74 @@
+use crate::metadata::{ContentHash, ContractMetadata};
+use ethers::utils::{hex, keccak256};
+use eyre::Result;
+use std::path::Path;
+
+/// Gateway used to fetch the metadata JSON when `--ipfs-gateway` is not set
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+
+/// Gateway used to fetch the metadata JSON of older contracts when `--swarm-gateway` is not set
+pub const DEFAULT_SWARM_GATEWAY: &str = "https://swarm-gateways.net/bzz-raw:/";
+
+/**
+ * The result of comparing the source files in the project with the hashes in the metadata JSON
+ */
+#[derive(Debug, Default)]
+pub struct SourceHashReport {
+    pub matched: Vec<String>,
+    /// Files whose content changed after the metadata was generated
+    pub mismatched: Vec<String>,
+    /// Files from the metadata that are not in the project
+    pub missing: Vec<String>,
+}
+
+/**
+ * Fetches the metadata JSON that the hash in the CBOR metadata points to.
+ * Swarm hashes (solc < 0.6) are only fetched when a swarm gateway is given.
+ */
+pub async fn fetch_metadata_json(ipfs_gateway: &str, swarm_gateway: Option<&str>, metadata: &ContractMetadata) -> Result<serde_json::Value> {
+    let url = match metadata.content_hash() {
+        Some(ContentHash::Ipfs(cid)) => format!("{}/{}", ipfs_gateway.trim_end_matches('/'), cid),
+        Some(ContentHash::Bzzr0(hash)) | Some(ContentHash::Bzzr1(hash)) => match swarm_gateway {
+            Some(gateway) => format!("{}/{}", gateway.trim_end_matches('/'), hash),
+            None => return Err(eyre::eyre!("The metadata contains a Swarm hash, use --fetch-swarm to fetch it from a Swarm gateway")),
+        },
+        None => return Err(eyre::eyre!("The metadata contains no IPFS or Swarm hash")),
+    };
+
+    let response = reqwest::get(&url).await?.error_for_status()?;
+    Ok(response.json().await?)
+}
+
+/**
+ * Hashes every source file listed in the metadata JSON and compares it against the stored `keccak256`
+ */
+pub async fn verify_source_hashes(
+    project_path: &Path,
+    metadata: &ContractMetadata,
+    ipfs_gateway: &str,
+    swarm_gateway: Option<&str>,
+) -> Result<SourceHashReport> {
+    let metadata_json = fetch_metadata_json(ipfs_gateway, swarm_gateway, metadata).await?;
+    let sources = metadata_json["sources"]
+        .as_object()
+        .ok_or_else(|| eyre::eyre!("The metadata JSON contains no sources"))?;
+
+    let mut report = SourceHashReport::default();
+    for (source_path, source) in sources {
+        let expected = source["keccak256"].as_str().unwrap_or_default().trim_start_matches("0x").to_lowercase();
+
+        // Imports from npm packages are not stored in the project itself
+        let mut file_path = project_path.join(source_path);
+        if !file_path.exists() {
+            file_path = project_path.join("node_modules").join(source_path);
+        }
+
+        match std::fs::read(&file_path) {
+            Ok(content) if hex::encode(keccak256(&content)) == expected => report.matched.push(source_path.clone()),
+            Ok(_) => report.mismatched.push(source_path.clone()),
+            Err(_) => report.missing.push(source_path.clone()),
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,133 @@
+use serde::Deserialize;
+
+/**
+ * The storage layout as printed by `forge inspect <Contract> storage-layout`, the type definitions are not needed
+ */
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+}
+
+/**
+ * A single state variable
+ */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StorageEntry {
+    pub label: String,
+    pub slot: String,
+    pub offset: u64,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+impl std::fmt::Display for StorageEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (slot {}, offset {}, {})", self.label, self.slot, self.offset, self.type_name)
+    }
+}
+
+/**
+ * How the storage layout changed between two versions of a contract, variables are matched by their name
+ */
+#[derive(Debug, Clone, Default)]
+pub struct StorageLayoutDiff {
+    pub added: Vec<StorageEntry>,
+    pub removed: Vec<StorageEntry>,
+    /// Variables whose slot, offset or type changed, as `(old, new)`
+    pub changed: Vec<(StorageEntry, StorageEntry)>,
+}
+
+impl StorageLayoutDiff {
+    pub fn compute(old: &StorageLayout, new: &StorageLayout) -> StorageLayoutDiff {
+        let mut diff = StorageLayoutDiff::default();
+
+        for old_entry in old.storage.iter() {
+            match new.storage.iter().find(|new_entry| new_entry.label == old_entry.label) {
+                Some(new_entry) if new_entry != old_entry => diff.changed.push((old_entry.clone(), new_entry.clone())),
+                Some(_) => {}
+                None => diff.removed.push(old_entry.clone()),
+            }
+        }
+
+        for new_entry in new.storage.iter() {
+            if !old.storage.iter().any(|old_entry| old_entry.label == new_entry.label) {
+                diff.added.push(new_entry.clone());
+            }
+        }
+
+        diff
+    }
+
+    /**
+     * Only appending new variables is safe for an upgrade
+     */
+    pub fn is_upgrade_safe(&self) -> bool {
+        self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/**
+ * A state variable of a proxy that shares its slot with a state variable of the implementation, writes through one of them
+ * corrupt the other
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+    pub proxy_entry: StorageEntry,
+    pub implementation_entry: StorageEntry,
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "proxy variable {} collides with implementation variable {}", self.proxy_entry, self.implementation_entry)
+    }
+}
+
+pub struct StorageCollisionChecker;
+
+impl StorageCollisionChecker {
+    /**
+     * Proxies that follow EIP-1967 keep their state in pseudo random slots, so any sequential slot the proxy itself uses
+     * is shared with the implementation
+     */
+    pub fn check(proxy_layout: &StorageLayout, impl_layout: &StorageLayout) -> Vec<Collision> {
+        let mut collisions = Vec::new();
+
+        for proxy_entry in proxy_layout.storage.iter() {
+            for impl_entry in impl_layout.storage.iter().filter(|impl_entry| impl_entry.slot == proxy_entry.slot) {
+                collisions.push(Collision {
+                    proxy_entry: proxy_entry.clone(),
+                    implementation_entry: impl_entry.clone(),
+                });
+            }
+        }
+
+        collisions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StorageCollisionChecker, StorageEntry, StorageLayout};
+
+    fn entry(label: &str, slot: &str) -> StorageEntry {
+        StorageEntry {
+            label: label.to_string(),
+            slot: slot.to_string(),
+            offset: 0,
+            type_name: "t_address".to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_shared_slots() {
+        let proxy = StorageLayout { storage: vec![entry("implementation", "0"), entry("admin", "1")] };
+        let implementation = StorageLayout { storage: vec![entry("owner", "0"), entry("balance", "5")] };
+
+        let collisions = StorageCollisionChecker::check(&proxy, &implementation);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].proxy_entry.label, "implementation");
+        assert_eq!(collisions[0].implementation_entry.label, "owner");
+
+        assert!(StorageCollisionChecker::check(&StorageLayout { storage: Vec::new() }, &implementation).is_empty());
+    }
+}
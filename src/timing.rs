@@ -0,0 +1,47 @@
+use crate::interrupt::run_command;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use eyre::Result;
+use std::{path::Path, process::Command};
+
+/**
+ * When the checked out commit was made relative to the deployment of the contract
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommitTimingStatus {
+    PredatesDeployment,
+    /// The source was changed after the deployment, `delta` is how long after
+    PostdatesDeployment { delta: Duration },
+    SameSecond,
+}
+
+/**
+ * The commit has to be older than the deployment, otherwise the source was likely modified after the contract was deployed
+ */
+pub fn verify_commit_predates_deployment(commit_ts: DateTime<Utc>, deploy_ts: DateTime<Utc>) -> CommitTimingStatus {
+    let delta = commit_ts - deploy_ts;
+    if delta > Duration::zero() {
+        CommitTimingStatus::PostdatesDeployment { delta }
+    } else if delta == Duration::zero() {
+        CommitTimingStatus::SameSecond
+    } else {
+        CommitTimingStatus::PredatesDeployment
+    }
+}
+
+/**
+ * The committer timestamp of the commit that is checked out in the project
+ */
+pub fn commit_timestamp(git: &Path, project_path: &Path) -> Result<DateTime<Utc>> {
+    let output = run_command(Command::new(git)
+        .args(["show", "-s", "--format=%ct", "HEAD"])
+        .current_dir(project_path))?;
+
+    if !output.status.success() {
+        return Err(eyre::eyre!("Could not read the commit timestamp: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let seconds: i64 = String::from_utf8_lossy(&output.stdout).trim().parse()?;
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .ok_or_else(|| eyre::eyre!("Invalid commit timestamp {}", seconds))
+}
@@ -0,0 +1,337 @@
+use crate::proof::sha256_hex;
+use ethers::{
+    providers::Middleware,
+    types::{Trace, H256},
+};
+use eyre::Result;
+use serde_json::{json, Value};
+use std::{env, path::PathBuf, str};
+
+/**
+ * How the RPC traces transactions: parity style `trace_transaction` (Erigon, Nethermind, Reth) or geth style
+ * `debug_traceTransaction` (Geth, BSC). `Auto` tries parity style first.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceProvider {
+    Parity,
+    Debug,
+    Auto,
+}
+
+impl str::FromStr for TraceProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "parity" => Ok(TraceProvider::Parity),
+            "debug" => Ok(TraceProvider::Debug),
+            "auto" => Ok(TraceProvider::Auto),
+            _ => Err(format!("Unknown trace provider type '{}', expected one of: parity, debug, auto", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for TraceProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceProvider::Parity => write!(f, "parity"),
+            TraceProvider::Debug => write!(f, "debug"),
+            TraceProvider::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl interactive_clap::ToCli for TraceProvider {
+    type CliVariant = TraceProvider;
+}
+
+/**
+ * Traces the transaction with the given provider type, geth style traces are converted to parity style ones
+ */
+pub async fn trace_transaction<M: Middleware>(provider: &M, tx_hash: H256, provider_type: TraceProvider) -> Result<Vec<Trace>> {
+    match provider_type {
+        TraceProvider::Parity => parity_trace_transaction(provider, tx_hash).await,
+        TraceProvider::Debug => debug_trace_transaction(provider, tx_hash, None).await,
+        TraceProvider::Auto => Ok(trace_auto(provider, tx_hash).await?.1),
+    }
+}
+
+/**
+ * Like `trace_transaction`, but in `Auto` mode the provider type that worked for the RPC is remembered, so later runs
+ * don't try parity style first on RPCs that only support geth style
+ */
+pub async fn trace_transaction_cached<M: Middleware>(provider: &M, tx_hash: H256, provider_type: TraceProvider, rpc: &str) -> Result<Vec<Trace>> {
+    if provider_type != TraceProvider::Auto {
+        return trace_transaction(provider, tx_hash, provider_type).await;
+    }
+
+    // The RPC may have been upgraded since, so a failure falls back to detecting it again
+    if let Some(cached) = cached_trace_provider(rpc) {
+        if let Ok(traces) = trace_transaction(provider, tx_hash, cached).await {
+            return Ok(traces);
+        }
+    }
+
+    let (resolved, traces) = trace_auto(provider, tx_hash).await?;
+    // The cache only saves a request, it is fine if it can't be written
+    let _ = cache_trace_provider(rpc, resolved);
+
+    Ok(traces)
+}
+
+/**
+ * Tries parity style tracing, then geth style, and returns the one that worked
+ */
+async fn trace_auto<M: Middleware>(provider: &M, tx_hash: H256) -> Result<(TraceProvider, Vec<Trace>)> {
+    let parity_err = match parity_trace_transaction(provider, tx_hash).await {
+        Ok(traces) => return Ok((TraceProvider::Parity, traces)),
+        Err(err) => err,
+    };
+
+    match debug_trace_transaction(provider, tx_hash, None).await {
+        Ok(traces) => Ok((TraceProvider::Debug, traces)),
+        Err(debug_err) => Err(eyre::eyre!("{}\n{}", parity_err, debug_err)),
+    }
+}
+
+async fn parity_trace_transaction<M: Middleware>(provider: &M, tx_hash: H256) -> Result<Vec<Trace>> {
+    provider
+        .trace_transaction(tx_hash)
+        .await
+        .map_err(|err| eyre::eyre!("Could not trace transaction {:?}: {}", tx_hash, err))
+}
+
+/**
+ * Traces the transaction with the `callTracer` of `debug_traceTransaction`, the timeout overrides the default of the
+ * RPC (5 seconds for geth)
+ */
+pub async fn debug_trace_transaction<M: Middleware>(provider: &M, tx_hash: H256, timeout_ms: Option<u64>) -> Result<Vec<Trace>> {
+    let mut config = json!({ "tracer": "callTracer" });
+    if let Some(timeout_ms) = timeout_ms {
+        config["timeout"] = json!(format!("{}ms", timeout_ms));
+    }
+
+    let frame: Value = provider
+        .provider()
+        .request("debug_traceTransaction", (tx_hash, config))
+        .await
+        .map_err(|err| eyre::eyre!("Could not trace transaction {:?} with debug_traceTransaction: {}", tx_hash, err))?;
+
+    // Geth traces don't say where the transaction is, parity traces do
+    let transaction = provider
+        .get_transaction(tx_hash)
+        .await
+        .map_err(|err| eyre::eyre!("Could not fetch transaction {:?}: {}", tx_hash, err))?
+        .ok_or_else(|| eyre::eyre!("Transaction {:?} does not exist", tx_hash))?;
+    let location = json!({
+        "transactionHash": tx_hash,
+        "transactionPosition": transaction.transaction_index.map(|index| index.as_u64()),
+        "blockNumber": transaction.block_number.map_or(0, |number| number.as_u64()),
+        "blockHash": transaction.block_hash.unwrap_or_default(),
+    });
+
+    let mut traces = Vec::new();
+    flatten_call_frame(&frame, Vec::new(), &location, &mut traces)?;
+
+    Ok(traces)
+}
+
+/**
+ * Converts a `callTracer` frame and its subcalls into parity style traces, in the same depth-first order parity uses
+ */
+fn flatten_call_frame(frame: &Value, trace_address: Vec<usize>, location: &Value, traces: &mut Vec<Trace>) -> Result<()> {
+    let call_type = frame["type"].as_str().unwrap_or("CALL").to_lowercase();
+    let value = frame.get("value").cloned().unwrap_or_else(|| json!("0x0"));
+    let output = frame.get("output").cloned().unwrap_or_else(|| json!("0x"));
+    let calls = frame["calls"].as_array().cloned().unwrap_or_default();
+    let error = frame.get("error").cloned().unwrap_or(Value::Null);
+
+    let (action_type, action, result) = match call_type.as_str() {
+        // The created address is in `to` and the deployed code in `output`
+        "create" | "create2" => (
+            "create",
+            json!({ "from": frame["from"], "value": value, "gas": frame["gas"], "init": frame["input"] }),
+            json!({ "gasUsed": frame["gasUsed"], "code": output, "address": frame["to"] }),
+        ),
+        "selfdestruct" => (
+            "suicide",
+            json!({ "address": frame["from"], "refundAddress": frame["to"], "balance": value }),
+            Value::Null,
+        ),
+        _ => (
+            "call",
+            json!({ "from": frame["from"], "to": frame["to"], "value": value, "gas": frame["gas"], "input": frame["input"], "callType": call_type }),
+            json!({ "gasUsed": frame["gasUsed"], "output": output }),
+        ),
+    };
+
+    let mut trace = json!({
+        "type": action_type,
+        "action": action,
+        "result": if error.is_null() { result } else { Value::Null },
+        "error": error,
+        "traceAddress": trace_address,
+        "subtraces": calls.len(),
+    });
+    if let (Some(trace), Some(location)) = (trace.as_object_mut(), location.as_object()) {
+        trace.extend(location.clone());
+    }
+    traces.push(serde_json::from_value(trace)?);
+
+    for (index, call) in calls.iter().enumerate() {
+        let mut address = trace_address.clone();
+        address.push(index);
+        flatten_call_frame(call, address, location, traces)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Location of the cache: `~/.config/bytematch/trace-providers.toml`, which maps hashes of RPC urls to `parity` or `debug`
+ */
+fn cache_path() -> Result<PathBuf> {
+    let home = env::var("HOME").map_err(|_| eyre::eyre!("Could not find the home directory to cache trace providers in"))?;
+
+    Ok(PathBuf::from(home).join(".config").join("bytematch").join("trace-providers.toml"))
+}
+
+fn load_cache() -> Result<toml::Table> {
+    let path = cache_path()?;
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/**
+ * RPC urls often contain an API key, only their hash is written to disk
+ */
+fn cache_key(rpc: &str) -> String {
+    sha256_hex(rpc.as_bytes())
+}
+
+fn cached_trace_provider(rpc: &str) -> Option<TraceProvider> {
+    load_cache().ok()?.get(&cache_key(rpc))?.as_str()?.parse().ok()
+}
+
+fn cache_trace_provider(rpc: &str, provider_type: TraceProvider) -> Result<()> {
+    let mut cache = load_cache()?;
+    cache.insert(cache_key(rpc), toml::Value::String(provider_type.to_string()));
+
+    let path = cache_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, toml::to_string(&cache)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache_key, trace_transaction, TraceProvider};
+    use crate::mock_provider::MockProvider;
+    use ethers::types::{Action, Address, Res, Transaction, H256, U64};
+    use serde_json::json;
+
+    fn transaction(hash: H256) -> Transaction {
+        Transaction {
+            hash,
+            transaction_index: Some(U64::from(3)),
+            block_number: Some(U64::from(100)),
+            block_hash: Some(H256::repeat_byte(9)),
+            ..Default::default()
+        }
+    }
+
+    /// A factory call that creates a contract, which calls back into a reverting contract
+    fn call_frame() -> serde_json::Value {
+        json!({
+            "type": "CALL",
+            "from": Address::repeat_byte(1),
+            "to": Address::repeat_byte(2),
+            "value": "0x0",
+            "gas": "0x100000",
+            "gasUsed": "0x5000",
+            "input": "0x12345678",
+            "output": "0x",
+            "calls": [{
+                "type": "CREATE2",
+                "from": Address::repeat_byte(2),
+                "to": Address::repeat_byte(3),
+                "value": "0x0",
+                "gas": "0x80000",
+                "gasUsed": "0x4000",
+                "input": "0x6080",
+                "output": "0x3350",
+                "calls": [{
+                    "type": "STATICCALL",
+                    "from": Address::repeat_byte(3),
+                    "to": Address::repeat_byte(4),
+                    "gas": "0x1000",
+                    "gasUsed": "0x1000",
+                    "input": "0x",
+                    "error": "execution reverted",
+                }],
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn flattens_call_tracer_frames() {
+        let tx_hash = H256::repeat_byte(1);
+        let mock = MockProvider::default().with_transaction(transaction(tx_hash)).with_rpc_response(call_frame());
+
+        let traces = trace_transaction(&mock, tx_hash, TraceProvider::Debug).await.unwrap();
+        let addresses: Vec<Vec<usize>> = traces.iter().map(|trace| trace.trace_address.clone()).collect();
+        assert_eq!(addresses, vec![vec![], vec![0], vec![0, 0]]);
+        assert_eq!(traces.iter().map(|trace| trace.subtraces).collect::<Vec<_>>(), vec![1, 1, 0]);
+        assert!(traces.iter().all(|trace| trace.transaction_hash == Some(tx_hash) && trace.block_number == 100));
+        assert_eq!(traces[0].transaction_position, Some(3));
+
+        match (&traces[1].action, &traces[1].result) {
+            (Action::Create(create), Some(Res::Create(result))) => {
+                assert_eq!(create.from, Address::repeat_byte(2));
+                assert_eq!(create.init.to_vec(), vec![0x60, 0x80]);
+                assert_eq!(result.address, Address::repeat_byte(3));
+                assert_eq!(result.code.to_vec(), vec![0x33, 0x50]);
+            }
+            other => panic!("Expected a create trace, got {:?}", other),
+        }
+
+        // A failed call has an error instead of a result
+        assert!(traces[2].result.is_none());
+        assert_eq!(traces[2].error.as_deref(), Some("execution reverted"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_debug_traces() {
+        let tx_hash = H256::repeat_byte(1);
+        let mock = MockProvider::default()
+            .with_trace_error(tx_hash, "the method trace_transaction does not exist")
+            .with_transaction(transaction(tx_hash))
+            .with_rpc_response(call_frame());
+
+        let traces = trace_transaction(&mock, tx_hash, TraceProvider::Auto).await.unwrap();
+        assert_eq!(traces.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn reports_both_errors_when_nothing_traces() {
+        let tx_hash = H256::repeat_byte(1);
+        let mock = MockProvider::default().with_trace_error(tx_hash, "trace_transaction is disabled");
+
+        let err = trace_transaction(&mock, tx_hash, TraceProvider::Auto).await.unwrap_err().to_string();
+        assert!(err.contains("trace_transaction is disabled"));
+        assert!(err.contains("debug_traceTransaction"));
+    }
+
+    #[test]
+    fn does_not_cache_the_rpc_url() {
+        let key = cache_key("https://mainnet.infura.io/v3/secret-api-key");
+        assert!(!key.contains("secret-api-key"));
+        assert_eq!(key.len(), 64);
+    }
+}
@@ -0,0 +1,107 @@
+use ethers::types::{Action, Address, Res, Trace, U256};
+use serde::Serialize;
+
+/**
+ * A single trace of a transaction, flattened for display
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceRow {
+    pub trace_address: Vec<usize>,
+    pub action_type: String,
+    pub from: Address,
+    /// Callee for calls, the created contract for creations
+    pub to: Option<Address>,
+    pub value: U256,
+    pub gas_used: Option<U256>,
+}
+
+impl TraceRow {
+    pub fn from_trace(trace: &Trace) -> TraceRow {
+        let (action_type, from, to, value) = match &trace.action {
+            Action::Call(call) => ("call", call.from, Some(call.to), call.value),
+            Action::Create(create) => ("create", create.from, None, create.value),
+            Action::Suicide(suicide) => ("selfdestruct", suicide.address, Some(suicide.refund_address), suicide.balance),
+            Action::Reward(reward) => ("reward", reward.author, None, reward.value),
+        };
+
+        let (to, gas_used) = match &trace.result {
+            Some(Res::Call(result)) => (to, Some(result.gas_used)),
+            Some(Res::Create(result)) => (Some(result.address), Some(result.gas_used)),
+            _ => (to, None),
+        };
+
+        TraceRow {
+            trace_address: trace.trace_address.clone(),
+            action_type: action_type.to_string(),
+            from,
+            to,
+            value,
+            gas_used,
+        }
+    }
+}
+
+/**
+ * Formats the traces as a table with one trace per line
+ */
+pub fn format_trace_table(rows: &[TraceRow]) -> String {
+    let mut output = format!(
+        "{:<16} {:<12} {:<42} {:<42} {:>24} {:>10}\n",
+        "trace", "type", "from", "to/created", "value", "gas used"
+    );
+
+    for row in rows {
+        let trace_address: Vec<String> = row.trace_address.iter().map(|index| index.to_string()).collect();
+        let to = row.to.map(|to| format!("{:?}", to)).unwrap_or_else(|| "-".to_string());
+        let gas_used = row.gas_used.map(|gas| gas.to_string()).unwrap_or_else(|| "-".to_string());
+
+        output.push_str(&format!(
+            "{:<16} {:<12} {:<42} {:<42} {:>24} {:>10}\n",
+            format!("[{}]", trace_address.join(",")),
+            row.action_type,
+            format!("{:?}", row.from),
+            to,
+            row.value.to_string(),
+            gas_used
+        ));
+    }
+
+    output
+}
+
+/**
+ * Formats the traces as a call tree, every trace is indented below the trace that made the call.
+ * The traces are walked depth-first in the order of their trace addresses.
+ */
+pub fn format_trace_tree(traces: &[Trace]) -> String {
+    let mut sorted: Vec<&Trace> = traces.iter().collect();
+    sorted.sort_by(|a, b| a.trace_address.cmp(&b.trace_address));
+
+    let subtraces = |address: &[usize]| {
+        sorted.iter().find(|trace| trace.trace_address == address).map_or(0, |trace| trace.subtraces)
+    };
+    // Whether the trace is the last call of its parent, which decides if the line of the parent continues below it
+    let is_last = |address: &[usize]| match address.split_last() {
+        Some((index, parent)) => index + 1 >= subtraces(parent),
+        None => true,
+    };
+
+    let mut output = String::new();
+    for trace in sorted.iter() {
+        let address = &trace.trace_address;
+
+        let mut prefix = String::new();
+        for depth in 1..address.len() {
+            prefix.push_str(if is_last(&address[..depth]) { "    " } else { "│   " });
+        }
+        if !address.is_empty() {
+            prefix.push_str(if is_last(address) { "└── " } else { "├── " });
+        }
+
+        let row = TraceRow::from_trace(trace);
+        let to = row.to.map(|to| format!("{:?}", to)).unwrap_or_else(|| "-".to_string());
+        output.push_str(&format!("{}{} {:?} -> {} (value: {})\n", prefix, row.action_type, row.from, to, row.value));
+    }
+
+    output
+}
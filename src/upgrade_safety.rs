@@ -0,0 +1,256 @@
+use crate::{
+    opcodes::{disassemble, Instruction, PUSH32},
+    storage_layout::StorageLayout,
+};
+use ethers::utils::hex;
+use std::collections::{BTreeMap, BTreeSet};
+
+const PUSH4: u8 = 0x63;
+const EQ: u8 = 0x14;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+const LOG1: u8 = 0xa1;
+const LOG4: u8 = 0xa4;
+/// `STOP`, `RETURN`, `REVERT` and `INVALID` end a block like a jump does
+const TERMINATING: [u8; 4] = [0x00, 0xf3, 0xfd, 0xfe];
+
+/**
+ * A version of a contract as it is deployed, the storage layout is only known when the source of the version is available
+ */
+#[derive(Debug, Clone)]
+pub struct DeployedContract {
+    pub code: Vec<u8>,
+    pub storage_layout: Option<StorageLayout>,
+}
+
+impl DeployedContract {
+    pub fn new(code: Vec<u8>, storage_layout: Option<StorageLayout>) -> DeployedContract {
+        DeployedContract { code, storage_layout }
+    }
+
+    /**
+     * The selectors of the function dispatcher (`PUSH4 <selector> EQ PUSH <destination> JUMPI`), with the opcodes of the block
+     * each one jumps to. Push data is left out of the block, so functions that only moved don't look changed.
+     */
+    fn functions(&self) -> BTreeMap<[u8; 4], Vec<u8>> {
+        let instructions = disassemble(&self.code);
+        let mut functions = BTreeMap::new();
+
+        for window in instructions.windows(4) {
+            let [push, eq, destination, jumpi] = window else { continue };
+            if push.opcode != PUSH4 || eq.opcode != EQ || jumpi.opcode != JUMPI || destination.push_data.is_empty() {
+                continue;
+            }
+
+            let selector: [u8; 4] = push.push_data.as_slice().try_into().unwrap_or_default();
+            let target = destination.push_data.iter().fold(0usize, |offset, byte| (offset << 8) | *byte as usize);
+            functions.insert(selector, block_opcodes(&instructions, target));
+        }
+
+        functions
+    }
+
+    /**
+     * Event topics are pushed as 32 byte values in the same block as the `LOG` that emits them
+     */
+    fn event_topics(&self) -> BTreeSet<[u8; 32]> {
+        let mut topics = BTreeSet::new();
+        let mut pushed: Vec<[u8; 32]> = Vec::new();
+
+        for instruction in disassemble(&self.code) {
+            match instruction.opcode {
+                JUMPDEST => pushed.clear(),
+                PUSH32 => pushed.extend(<[u8; 32]>::try_from(instruction.push_data.as_slice())),
+                LOG1..=LOG4 => topics.extend(pushed.drain(..)),
+                _ => {}
+            }
+        }
+
+        topics
+    }
+}
+
+/**
+ * The opcodes from the jump destination to the end of its block
+ */
+fn block_opcodes(instructions: &[Instruction], target: usize) -> Vec<u8> {
+    instructions
+        .iter()
+        .skip_while(|instruction| instruction.offset != target)
+        .skip(1)
+        .take_while(|instruction| instruction.opcode != JUMPDEST)
+        .scan(false, |ended, instruction| {
+            if *ended {
+                return None;
+            }
+            *ended = instruction.opcode == JUMP || TERMINATING.contains(&instruction.opcode);
+            Some(instruction.opcode)
+        })
+        .collect()
+}
+
+/**
+ * What changed between the deployed version of a contract and its upgrade. Functions are identified by their hex selector,
+ * events by their hex topic.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct UpgradeSafetyReport {
+    pub functions_added: Vec<String>,
+    pub functions_removed: Vec<String>,
+    /// Same selector, but the code the dispatcher jumps to differs
+    pub functions_changed: Vec<String>,
+    pub events_added: Vec<String>,
+    pub events_removed: Vec<String>,
+    /// New variables that occupy the slot and offset of a different old variable, only known when both storage layouts are
+    pub storage_collisions: Vec<String>,
+}
+
+impl UpgradeSafetyReport {
+    pub fn compare(old: &DeployedContract, new: &DeployedContract) -> UpgradeSafetyReport {
+        let mut report = UpgradeSafetyReport::default();
+
+        let (old_functions, new_functions) = (old.functions(), new.functions());
+        for (selector, body) in new_functions.iter() {
+            match old_functions.get(selector) {
+                Some(old_body) if old_body != body => report.functions_changed.push(format!("0x{}", hex::encode(selector))),
+                Some(_) => {}
+                None => report.functions_added.push(format!("0x{}", hex::encode(selector))),
+            }
+        }
+        for selector in old_functions.keys().filter(|selector| !new_functions.contains_key(*selector)) {
+            report.functions_removed.push(format!("0x{}", hex::encode(selector)));
+        }
+
+        let (old_events, new_events) = (old.event_topics(), new.event_topics());
+        for topic in new_events.difference(&old_events) {
+            report.events_added.push(format!("0x{}", hex::encode(topic)));
+        }
+        for topic in old_events.difference(&new_events) {
+            report.events_removed.push(format!("0x{}", hex::encode(topic)));
+        }
+
+        if let (Some(old_layout), Some(new_layout)) = (old.storage_layout.as_ref(), new.storage_layout.as_ref()) {
+            for new_entry in new_layout.storage.iter() {
+                let collision = old_layout.storage.iter().find(|old_entry| {
+                    old_entry.slot == new_entry.slot
+                        && old_entry.offset == new_entry.offset
+                        && (old_entry.label != new_entry.label || old_entry.type_name != new_entry.type_name)
+                });
+                if let Some(old_entry) = collision {
+                    report.storage_collisions.push(format!("{} overlaps {}", new_entry, old_entry));
+                }
+            }
+        }
+
+        report
+    }
+
+    /**
+     * Removed functions break callers and storage collisions corrupt state, everything else is reported for review
+     */
+    pub fn is_safe(&self) -> bool {
+        self.functions_removed.is_empty() && self.storage_collisions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block_opcodes, DeployedContract, UpgradeSafetyReport};
+    use crate::{
+        opcodes::disassemble,
+        storage_layout::{StorageEntry, StorageLayout},
+    };
+
+    const TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+    const APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+    const MINT: [u8; 4] = [0x40, 0xc1, 0x0f, 0x19];
+
+    /// A dispatcher of `PUSH4 <selector> EQ PUSH1 <destination> JUMPI` followed by `JUMPDEST <body>` for every function
+    fn contract(functions: &[([u8; 4], &[u8])]) -> Vec<u8> {
+        let mut code = Vec::new();
+        let mut bodies = Vec::new();
+        let mut destination = functions.len() * 9 + 1;
+        for (selector, body) in functions {
+            code.push(0x63);
+            code.extend(selector);
+            code.extend([0x14, 0x60, destination as u8, 0x57]);
+
+            bodies.push(0x5b);
+            bodies.extend(*body);
+            destination += body.len() + 1;
+        }
+        code.push(0x00);
+        code.extend(bodies);
+
+        code
+    }
+
+    fn layout(entries: &[(&str, &str)]) -> Option<StorageLayout> {
+        let storage = entries
+            .iter()
+            .map(|(label, slot)| StorageEntry { label: label.to_string(), slot: slot.to_string(), offset: 0, type_name: "t_uint256".to_string() })
+            .collect();
+
+        Some(StorageLayout { storage })
+    }
+
+    #[test]
+    fn takes_the_block_up_to_the_jump() {
+        // `JUMPDEST PUSH1 0x01 PUSH1 0x10 JUMP CALLER JUMPDEST STOP`
+        let instructions = disassemble(&[0x5b, 0x60, 0x01, 0x60, 0x10, 0x56, 0x33, 0x5b, 0x00]);
+
+        assert_eq!(block_opcodes(&instructions, 0), vec![0x60, 0x60, 0x56]);
+        assert_eq!(block_opcodes(&instructions, 7), vec![0x00]);
+        assert!(block_opcodes(&instructions, 100).is_empty());
+    }
+
+    #[test]
+    fn compares_the_functions() {
+        let old = contract(&[(TRANSFER, &[0x60, 0x01, 0x00]), (APPROVE, &[0x33, 0x00])]);
+        // `transfer` only pushes a different value, `approve` is gone and `mint` is new
+        let new = contract(&[(TRANSFER, &[0x60, 0x02, 0x00]), (MINT, &[0x33, 0x00])]);
+
+        let report = UpgradeSafetyReport::compare(&DeployedContract::new(old.clone(), None), &DeployedContract::new(new, None));
+        assert_eq!(report.functions_added, vec!["0x40c10f19"]);
+        assert_eq!(report.functions_removed, vec!["0x095ea7b3"]);
+        assert!(report.functions_changed.is_empty());
+        assert!(!report.is_safe());
+
+        let changed = contract(&[(TRANSFER, &[0x80, 0x00]), (APPROVE, &[0x33, 0x00])]);
+        let report = UpgradeSafetyReport::compare(&DeployedContract::new(old, None), &DeployedContract::new(changed, None));
+        assert_eq!(report.functions_changed, vec!["0xa9059cbb"]);
+        assert!(report.is_safe());
+    }
+
+    #[test]
+    fn compares_the_events() {
+        let emit = |topic: u8| {
+            let mut body = vec![0x7f];
+            body.extend([topic; 32]);
+            body.extend([0x60, 0x00, 0x80, 0xa1, 0x00]);
+            body
+        };
+        let old = contract(&[(TRANSFER, &emit(0x11))]);
+        let new = contract(&[(TRANSFER, &emit(0x22))]);
+
+        let report = UpgradeSafetyReport::compare(&DeployedContract::new(old, None), &DeployedContract::new(new, None));
+        assert_eq!(report.events_added, vec![format!("0x{}", "22".repeat(32))]);
+        assert_eq!(report.events_removed, vec![format!("0x{}", "11".repeat(32))]);
+    }
+
+    #[test]
+    fn finds_storage_collisions_only_with_both_layouts() {
+        let code = contract(&[(TRANSFER, &[0x00])]);
+        let old = DeployedContract::new(code.clone(), layout(&[("owner", "0"), ("balance", "1")]));
+        let new = DeployedContract::new(code.clone(), layout(&[("owner", "0"), ("paused", "1"), ("balance", "2")]));
+
+        let report = UpgradeSafetyReport::compare(&old, &new);
+        assert_eq!(report.storage_collisions.len(), 1);
+        assert!(report.storage_collisions[0].starts_with("paused"));
+        assert!(!report.is_safe());
+
+        let report = UpgradeSafetyReport::compare(&old, &DeployedContract::new(code, None));
+        assert!(report.storage_collisions.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use ethers::{
+    abi::{encode, Token},
+    providers::{Http, Middleware, Provider},
+    types::{Address, TransactionRequest, H256, U256},
+    utils::hex,
+};
+use std::{
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+use which::which;
+
+const COUNTER: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+contract Counter {
+    uint256 public number;
+
+    function increment() public {
+        number += 1;
+    }
+}
+"#;
+
+const STORED: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+contract Stored {
+    uint256 public value;
+
+    constructor(uint256 _value) {
+        value = _value;
+    }
+}
+"#;
+
+/**
+ * A local anvil node, killed when it goes out of scope
+ */
+struct Anvil {
+    child: tokio::process::Child,
+    port: u16,
+}
+
+impl Anvil {
+    /**
+     * Starts anvil on a free port, returns `None` when anvil or forge are not installed so the test can be skipped
+     */
+    async fn spawn(test: &str) -> Option<Anvil> {
+        let (anvil, forge) = (which("anvil"), which("forge"));
+        let anvil = match (anvil, forge) {
+            (Ok(anvil), Ok(_)) => anvil,
+            _ => {
+                eprintln!("Skipping {}: anvil and forge have to be installed", test);
+                return None;
+            }
+        };
+
+        // The OS hands out a free port, it is released again right before anvil binds it
+        let port = TcpListener::bind(("127.0.0.1", 0)).unwrap().local_addr().unwrap().port();
+
+        let child = tokio::process::Command::new(anvil)
+            .args(["--port", &port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Could not start anvil");
+
+        // Wait until anvil accepts connections
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return Some(Anvil { child, port });
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        panic!("anvil did not start on port {}", port);
+    }
+
+    fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+}
+
+impl Drop for Anvil {
+    fn drop(&mut self) {
+        let _ = self.child.start_kill();
+    }
+}
+
+/**
+ * Creates a foundry project with a single contract in a fresh folder, optionally committed to a new git repository.
+ * The name is part of the commit message, so every test gets its own commit and with it its own checkout folder.
+ */
+fn create_project(name: &str, contract_name: &str, source: &str, commit: bool) -> (PathBuf, Option<String>) {
+    let path = std::env::temp_dir().join(format!("bytematch-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("src")).unwrap();
+
+    std::fs::write(path.join("foundry.toml"), "[profile.default]\nsrc = \"src\"\nout = \"out\"\nlibs = []\n").unwrap();
+    std::fs::write(path.join("src").join(format!("{}.sol", contract_name)), source).unwrap();
+    std::fs::write(path.join(".gitignore"), "out/\ncache/\n").unwrap();
+
+    if !commit {
+        return (path, None);
+    }
+
+    let git = |args: &[&str]| {
+        let output = Command::new("git").args(args).current_dir(&path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "-A"]);
+    git(&["-c", "user.name=bytematch", "-c", "user.email=bytematch@localhost", "commit", "--quiet", "-m", &format!("Add contract ({})", name)]);
+    let hash = git(&["rev-parse", "HEAD"]);
+
+    (path, Some(hash))
+}
+
+/**
+ * Compiles the contract of the project and deploys it from the first anvil account, with the ABI encoded constructor arguments
+ */
+async fn deploy(url: &str, project: &Path, contract_name: &str, constructor_args: &[Token]) -> (Address, H256) {
+    let output = Command::new("forge")
+        .args(["inspect", contract_name, "bytecode"])
+        .current_dir(project)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "forge inspect failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let mut init_code = hex::decode(String::from_utf8_lossy(&output.stdout).trim().trim_start_matches("0x")).unwrap();
+    init_code.extend(encode(constructor_args));
+
+    let provider = Provider::<Http>::try_from(url).unwrap();
+    let deployer = provider.get_accounts().await.unwrap()[0];
+    let receipt = provider
+        .send_transaction(TransactionRequest::new().from(deployer).data(init_code), None)
+        .await
+        .unwrap()
+        .await
+        .unwrap()
+        .expect("The deployment was not mined");
+
+    (receipt.contract_address.expect("No contract was created"), receipt.transaction_hash)
+}
+
+/**
+ * Runs the whole verification and returns the JSON result
+ */
+fn verify(url: &str, repository: &Path, commit: &str, contract_name: &str, address: Address, tx_hash: H256) -> serde_json::Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_bytematch"))
+        .args([
+            "--no-interactive",
+            "--no-spinner",
+            "--quiet",
+            "--output=json",
+            &format!("--transaction={:?}", tx_hash),
+            &format!("--contract-address={:?}", address),
+            &format!("--git={}", repository.display()),
+            &format!("--commit={}", commit),
+            &format!("--contract-name={}", contract_name),
+            &format!("--rpc={}", url),
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "bytematch failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    serde_json::from_slice(&output.stdout).expect("bytematch did not print JSON")
+}
+
+#[tokio::test]
+async fn matches_deployed_source() {
+    let Some(anvil) = Anvil::spawn("matches_deployed_source").await else { return };
+    let (repository, commit) = create_project("match", "Counter", COUNTER, true);
+
+    let (address, tx_hash) = deploy(&anvil.url(), &repository, "Counter", &[]).await;
+    let result = verify(&anvil.url(), &repository, &commit.unwrap(), "Counter", address, tx_hash);
+
+    assert_eq!(result["matched"], true);
+    assert_eq!(result["match_kind"], "exact");
+}
+
+#[tokio::test]
+async fn does_not_match_different_source() {
+    let Some(anvil) = Anvil::spawn("does_not_match_different_source").await else { return };
+    let (repository, commit) = create_project("no-match", "Counter", COUNTER, true);
+    let (deployed, _) = create_project("no-match-deployed", "Counter", &COUNTER.replace("+= 1", "+= 2"), false);
+
+    let (address, tx_hash) = deploy(&anvil.url(), &deployed, "Counter", &[]).await;
+    let result = verify(&anvil.url(), &repository, &commit.unwrap(), "Counter", address, tx_hash);
+
+    assert_eq!(result["matched"], false);
+    assert_eq!(result["match_kind"], "no_match");
+}
+
+#[tokio::test]
+async fn ignores_metadata_only_difference() {
+    let Some(anvil) = Anvil::spawn("ignores_metadata_only_difference").await else { return };
+    let (repository, commit) = create_project("metadata", "Counter", COUNTER, true);
+    // A comment changes the hash of the source in the metadata, but not the code
+    let (deployed, _) = create_project(
+        "metadata-deployed",
+        "Counter",
+        &COUNTER.replace("contract Counter", "// Deployed version\ncontract Counter"),
+        false,
+    );
+
+    let (address, tx_hash) = deploy(&anvil.url(), &deployed, "Counter", &[]).await;
+    let result = verify(&anvil.url(), &repository, &commit.unwrap(), "Counter", address, tx_hash);
+
+    assert_eq!(result["matched"], true);
+}
+
+#[tokio::test]
+async fn strips_constructor_arguments() {
+    let Some(anvil) = Anvil::spawn("strips_constructor_arguments").await else { return };
+    let (repository, commit) = create_project("constructor", "Stored", STORED, true);
+
+    let (address, tx_hash) = deploy(&anvil.url(), &repository, "Stored", &[Token::Uint(U256::from(42))]).await;
+    let result = verify(&anvil.url(), &repository, &commit.unwrap(), "Stored", address, tx_hash);
+
+    assert_eq!(result["matched"], true);
+}